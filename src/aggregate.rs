@@ -0,0 +1,336 @@
+use crate::internal::node_impl::NodeImpl;
+use crate::internal::observers::ObserverCell;
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::observer::{TreeEvent, TreeObserver};
+use crate::tree::Tree;
+use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::tree_element_type::Node;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::{Rc, Weak};
+
+// Summarizes the values of a subtree, e.g. the sum of all leaf values.
+// The aggregate of a node is from_node of its own value combined with the aggregates of all
+// of its children from first to last, so combine has to be associative.
+pub trait Aggregate<IT, LT> {
+    type Output: Clone + 'static;
+
+    fn from_leaf(value: &LT) -> Self::Output;
+    fn from_node(value: &IT) -> Self::Output;
+    fn combine(left: Self::Output, right: &Self::Output) -> Self::Output;
+}
+
+// Keeps the aggregates stored in the nodes up to date. Removed subtrees lose their aggregates,
+// because changes of detached elements are not reported to the tree they have been removed from.
+// While the value of an element is mutably borrowed, its ancestors are not updated. They are
+// updated when the guard is dropped and the change of the value is reported.
+struct AggregateObserver<A> {
+    aggregate: PhantomData<A>,
+}
+
+impl<A> AggregateObserver<A> {
+    // Returns None if the value of the element is mutably borrowed
+    fn aggregate_of<IT, LT>(&self, element: &TreeElementImpl<IT, LT>) -> Option<A::Output>
+    where
+        A: Aggregate<IT, LT>,
+    {
+        match element {
+            TreeElementImpl::Leaf(l) => Some(A::from_leaf(&l.try_borrow().ok()?.value)),
+            TreeElementImpl::Node(n) => {
+                let cached = n
+                    .try_borrow()
+                    .ok()?
+                    .aggregate
+                    .borrow()
+                    .as_ref()
+                    .and_then(|a| a.downcast_ref::<A::Output>())
+                    .cloned();
+                cached.or_else(|| self.compute(n))
+            }
+        }
+    }
+
+    // Calculates the aggregate of node from the aggregates of its children
+    fn recalculate<IT, LT>(&self, node: &Rc<RefCell<NodeImpl<IT, LT>>>) -> Option<A::Output>
+    where
+        A: Aggregate<IT, LT>,
+    {
+        let node = node.try_borrow().ok()?;
+        let mut result = A::from_node(&node.value);
+        for child in node.children.iter() {
+            result = A::combine(result, &self.aggregate_of(child)?);
+        }
+        node.aggregate.replace(Some(Box::new(result.clone())));
+        Some(result)
+    }
+
+    // Calculates the aggregates of all nodes below node that have no aggregate yet
+    fn compute<IT, LT>(&self, node: &Rc<RefCell<NodeImpl<IT, LT>>>) -> Option<A::Output>
+    where
+        A: Aggregate<IT, LT>,
+    {
+        // The aggregate of a node is calculated after the aggregates of all of its children
+        let mut stack = vec![(node.clone(), false)];
+        while let Some((current, children_done)) = stack.pop() {
+            if children_done {
+                self.recalculate(&current)?;
+            } else if current.try_borrow().ok()?.aggregate.borrow().is_none() {
+                stack.push((current.clone(), true));
+                for child in current.borrow().children.iter() {
+                    if let TreeElementImpl::Node(n) = child {
+                        stack.push((n.clone(), false));
+                    }
+                }
+            }
+        }
+        self.aggregate_of(&TreeElementImpl::Node(node.clone()))
+    }
+
+    // Recalculates the aggregates of node and all of its ancestors
+    fn update<IT, LT>(&self, node: &Node<IT, LT>)
+    where
+        A: Aggregate<IT, LT>,
+    {
+        let mut current = Some(node.element_impl.clone());
+        while let Some(node) = current {
+            if self.recalculate(&node).is_none() {
+                return;
+            }
+            current = node.borrow().parent.as_ref().and_then(Weak::upgrade);
+        }
+    }
+}
+
+fn clear<IT, LT>(element: &TreeElement<IT, LT>) {
+    let mut stack = match element {
+        TreeElement::Node(n) => vec![n.element_impl.clone()],
+        TreeElement::Leaf(_) => vec![],
+    };
+    while let Some(current) = stack.pop() {
+        // A node whose value is mutably borrowed keeps its aggregates
+        let current = match current.try_borrow() {
+            Ok(current) => current,
+            Err(_) => continue,
+        };
+        current.aggregate.replace(None);
+        for child in current.children.iter() {
+            if let TreeElementImpl::Node(n) = child {
+                stack.push(n.clone());
+            }
+        }
+    }
+}
+
+impl<IT, LT, A: Aggregate<IT, LT>> TreeObserver<IT, LT> for AggregateObserver<A> {
+    fn on_event(&mut self, event: &TreeEvent<IT, LT>) {
+        match event {
+            TreeEvent::ChildInserted { parent, .. }
+            | TreeEvent::ChildrenReordered { parent, .. } => self.update(parent),
+            TreeEvent::ChildRemoved {
+                parent, removed, ..
+            } => {
+                clear(removed);
+                self.update(parent);
+            }
+            TreeEvent::ElementReplaced { parent, old, .. } => {
+                clear(old);
+                self.update(parent);
+            }
+            TreeEvent::ValueChanged { element } => {
+                let changed = match element {
+                    TreeElement::Node(n) => Some(n.clone()),
+                    TreeElement::Leaf(l) => l.parent().ok().flatten(),
+                };
+                if let Some(changed) = changed {
+                    self.update(&changed);
+                }
+            }
+        }
+    }
+}
+
+impl<IT, LT> Node<IT, LT> {
+    // Returns None if no aggregate is enabled or if T is not the output of the enabled aggregate
+    pub fn aggregate<T: Clone + 'static>(&self) -> Option<T> {
+        self.element_impl
+            .borrow()
+            .aggregate
+            .borrow()
+            .as_ref()
+            .and_then(|a| a.downcast_ref::<T>())
+            .cloned()
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    // Stores the aggregate of its subtree in every node and updates it on each change of the tree.
+    // An already enabled aggregate is replaced.
+    pub fn enable_aggregate<A>(&self)
+    where
+        A: Aggregate<IT, LT> + 'static,
+    {
+        self.disable_aggregate();
+        let observer = AggregateObserver::<A> {
+            aggregate: PhantomData,
+        };
+        if let TreeElementImpl::Node(n) = &self.tree {
+            observer.compute(n);
+        }
+        let mut observers = self.tree.observers_mut();
+        let id = observers.add(Rc::new(ObserverCell::new(observer)));
+        observers.aggregate = Some(id);
+    }
+
+    pub fn disable_aggregate(&self) {
+        let id = self.tree.observers_mut().aggregate.take();
+        if let Some(id) = id {
+            self.tree.observers_mut().remove(id);
+            clear(&self.root_node());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aggregate::Aggregate;
+    use crate::test_util::{build_tree, child};
+    use crate::tree::{DLTreeError, Tree, Value};
+
+    // The sum of all leaves
+    struct Sum;
+
+    impl Aggregate<String, u32> for Sum {
+        type Output = u32;
+
+        fn from_leaf(value: &u32) -> u32 {
+            *value
+        }
+        fn from_node(_value: &String) -> u32 {
+            0
+        }
+        fn combine(left: u32, right: &u32) -> u32 {
+            left + right
+        }
+    }
+
+    // The values of all nodes in depth-first order
+    struct Outline;
+
+    impl Aggregate<String, u32> for Outline {
+        type Output = String;
+
+        fn from_leaf(_value: &u32) -> String {
+            String::new()
+        }
+        fn from_node(value: &String) -> String {
+            value.clone()
+        }
+        fn combine(left: String, right: &String) -> String {
+            left + right
+        }
+    }
+
+    #[test]
+    fn sum_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        let root = tree.root_node().as_node().unwrap();
+        assert_eq!(root.aggregate::<u32>(), None);
+        tree.enable_aggregate::<Sum>();
+        assert_eq!(root.aggregate::<u32>(), Some(15));
+        assert_eq!(root.aggregate::<String>(), None);
+
+        let mut b = child(&tree.root_node(), 1).as_node().unwrap();
+        let c = child(&child(&tree.root_node(), 1), 1).as_node().unwrap();
+        assert_eq!(b.aggregate(), Some(5u32));
+
+        *c.children()[0].as_leaf().unwrap().value_mut() = 30;
+        assert_eq!(root.aggregate(), Some(42u32));
+        assert_eq!(c.aggregate(), Some(30u32));
+
+        b.push_front_child(Value::Leaf(100));
+        assert_eq!(b.aggregate(), Some(132u32));
+        let mut removed = b.remove_from_tree()?;
+        assert_eq!(root.aggregate(), Some(10u32));
+        assert_eq!(b.aggregate::<u32>(), None);
+
+        // Changes of the removed subtree are taken into account when it is inserted again
+        *c.children()[0].as_leaf().unwrap().value_mut() = 1;
+        removed.root_node().as_node().unwrap().children()[0]
+            .as_leaf()
+            .unwrap()
+            .set_node("f".to_string())?;
+        root.clone().push_child_tree(removed.root_node());
+        assert_eq!(root.aggregate(), Some(13u32));
+        assert_eq!(c.aggregate(), Some(1u32));
+
+        tree.disable_aggregate();
+        assert_eq!(root.aggregate::<u32>(), None);
+        removed = Tree::new(Value::Leaf(1));
+        root.clone().push_child_tree(removed.root_node());
+        assert_eq!(root.aggregate::<u32>(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn borrowed_value_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        tree.enable_aggregate::<Sum>();
+        let root = tree.root_node().as_node().unwrap();
+        let mut b = child(&tree.root_node(), 1).as_node().unwrap();
+        let c = child(&child(&tree.root_node(), 1), 1).as_node().unwrap();
+
+        // Changes below a mutably borrowed value update the aggregates when the borrow ends
+        let guard = root.value_mut();
+        b.push_back_child(Value::Leaf(5));
+        drop(guard);
+        assert_eq!(root.aggregate(), Some(20u32));
+        assert_eq!(b.aggregate(), Some(10u32));
+
+        // Aggregates are updated while values are read
+        let value = root.value();
+        b.push_front_child(Value::Leaf(5));
+        assert_eq!(root.aggregate(), Some(25u32));
+        drop(value);
+
+        // A sibling that is mutably borrowed delays the update until its guard is dropped
+        let mut guard = c.value_mut();
+        b.children()[0].clone().set_leaf(1)?;
+        *guard = "f".to_string();
+        drop(guard);
+        assert_eq!(b.aggregate(), Some(11u32));
+        assert_eq!(root.aggregate(), Some(21u32));
+        Ok(())
+    }
+
+    #[test]
+    fn outline_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        tree.enable_aggregate::<Outline>();
+        let mut root = tree.root_node().as_node().unwrap();
+        assert_eq!(root.aggregate(), Some("abcde".to_string()));
+
+        *child(&tree.root_node(), 2).as_node().unwrap().value_mut() = "x".to_string();
+        assert_eq!(root.aggregate(), Some("abcxe".to_string()));
+        *root.value_mut() = "root".to_string();
+        assert_eq!(root.aggregate(), Some("rootbcxe".to_string()));
+
+        root.sort_children_unstable(|a, b| b.as_node().is_some().cmp(&a.as_node().is_some()));
+        root.sort_children_unstable(|a, b| match (a.as_node(), b.as_node()) {
+            (Some(a), Some(b)) => b.value().cmp(&a.value()),
+            _ => std::cmp::Ordering::Equal,
+        });
+        assert_eq!(root.aggregate(), Some("rootxebc".to_string()));
+
+        // The journal replays edits through the same events
+        tree.enable_journal(10);
+        child(&tree.root_node(), 1)
+            .as_node()
+            .unwrap()
+            .remove_all_children()?;
+        assert_eq!(root.aggregate(), Some("rootxeb".to_string()));
+        // Every removed child is a single edit
+        while tree.undo()? {}
+        assert_eq!(root.aggregate(), Some("rootxebc".to_string()));
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use crate::tree::DLTreeError;
+use crate::tree_elements::tree_element::TreeElement;
+
+// The lowest common ancestor of two elements and the number of steps from both elements up to it
+struct CommonAncestor<IT, LT> {
+    ancestor: TreeElement<IT, LT>,
+    own_steps: usize,
+    other_steps: usize,
+}
+
+impl<IT, LT> TreeElement<IT, LT> {
+    // Both handles refer to the same element
+    pub fn ptr_eq(&self, other: &TreeElement<IT, LT>) -> bool {
+        self.to_impl().ptr_eq(&other.to_impl())
+    }
+
+    // The element itself followed by its parent, grandparent etc. up to the root
+    fn ancestor_chain(&self) -> Result<Vec<TreeElement<IT, LT>>, DLTreeError> {
+        let mut chain = vec![self.clone()];
+        while let Some(parent) = chain[chain.len() - 1].parent()? {
+            chain.push(TreeElement::Node(parent));
+        }
+        Ok(chain)
+    }
+
+    fn common_ancestor(
+        &self,
+        other: &TreeElement<IT, LT>,
+    ) -> Result<CommonAncestor<IT, LT>, DLTreeError> {
+        let mut own = self.ancestor_chain()?;
+        let others = other.ancestor_chain()?;
+        // Walk down from the roots while both chains agree
+        let mut common = 0;
+        while common < own.len()
+            && common < others.len()
+            && own[own.len() - 1 - common].ptr_eq(&others[others.len() - 1 - common])
+        {
+            common += 1;
+        }
+        if common == 0 {
+            return Err(DLTreeError::DifferentTrees);
+        }
+        let own_steps = own.len() - common;
+        Ok(CommonAncestor {
+            ancestor: own.swap_remove(own_steps),
+            own_steps,
+            other_steps: others.len() - common,
+        })
+    }
+
+    pub fn same_tree(&self, other: &TreeElement<IT, LT>) -> Result<bool, DLTreeError> {
+        match self.common_ancestor(other) {
+            Ok(_) => Ok(true),
+            Err(DLTreeError::DifferentTrees) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    // An element is its own lowest common ancestor with itself and with its descendants
+    pub fn lowest_common_ancestor(
+        &self,
+        other: &TreeElement<IT, LT>,
+    ) -> Result<TreeElement<IT, LT>, DLTreeError> {
+        Ok(self.common_ancestor(other)?.ancestor)
+    }
+
+    // Only proper ancestors are considered, an element is no ancestor of itself
+    pub fn is_ancestor_of(&self, other: &TreeElement<IT, LT>) -> Result<bool, DLTreeError> {
+        let common = self.common_ancestor(other)?;
+        Ok(common.own_steps == 0 && common.other_steps > 0)
+    }
+
+    pub fn is_descendant_of(&self, other: &TreeElement<IT, LT>) -> Result<bool, DLTreeError> {
+        other.is_ancestor_of(self)
+    }
+
+    // The number of edges on the path between both elements
+    pub fn distance(&self, other: &TreeElement<IT, LT>) -> Result<usize, DLTreeError> {
+        let common = self.common_ancestor(other)?;
+        Ok(common.own_steps + common.other_steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{build_tree, child};
+    use crate::tree::{DLTreeError, Tree, Value};
+
+    #[test]
+    fn ancestor_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        let a = tree.root_node();
+        let one = child(&a, 0);
+        let b = child(&a, 1);
+        let c = child(&b, 1);
+        let three = child(&c, 0);
+        let four = child(&child(&a, 2), 0);
+
+        assert!(a.is_ancestor_of(&three)?);
+        assert!(b.is_ancestor_of(&c)?);
+        assert!(!c.is_ancestor_of(&b)?);
+        assert!(!c.is_ancestor_of(&c)?);
+        assert!(!one.is_ancestor_of(&four)?);
+        assert!(three.is_descendant_of(&b)?);
+        assert!(!b.is_descendant_of(&three)?);
+
+        assert!(three.lowest_common_ancestor(&four)?.ptr_eq(&a));
+        assert!(three.lowest_common_ancestor(&child(&b, 0))?.ptr_eq(&b));
+        assert!(b.lowest_common_ancestor(&three)?.ptr_eq(&b));
+        assert!(one.lowest_common_ancestor(&one)?.ptr_eq(&one));
+
+        assert_eq!(three.distance(&four)?, 5);
+        assert_eq!(three.distance(&b)?, 2);
+        assert_eq!(a.distance(&a)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn different_trees_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        let other = build_tree();
+        let b = child(&tree.root_node(), 1);
+        assert!(b.same_tree(&tree.root_node())?);
+        assert!(!b.same_tree(&other.root_node())?);
+        assert_eq!(
+            b.lowest_common_ancestor(&other.root_node()).unwrap_err(),
+            DLTreeError::DifferentTrees
+        );
+        assert_eq!(
+            b.distance(&other.root_node()),
+            Err(DLTreeError::DifferentTrees)
+        );
+
+        // A removed subtree is a tree on its own
+        let mut c = child(&b, 1);
+        let removed = c.remove_from_tree()?;
+        assert!(!c.same_tree(&b)?);
+        assert!(c.ptr_eq(&removed.root_node()));
+        assert_eq!(c.is_ancestor_of(&b), Err(DLTreeError::DifferentTrees));
+
+        let leaf = Tree::<String, u32>::new(Value::Leaf(1)).root_node();
+        assert!(leaf.same_tree(&leaf)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,756 @@
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{DLTreeError, Tree, Value};
+use crate::tree_elements::tree_element::TreeElement;
+use crate::DeepClone;
+use std::cell::{Ref, RefCell, RefMut};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+// Position of an element in the arena. The generation detects handles to removed elements
+// even if their slot has been reused by another element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ElementIndex {
+    index: usize,
+    generation: u64,
+}
+
+#[derive(Debug, Clone)]
+enum ArenaData<IT, LT> {
+    Node {
+        value: IT,
+        children: VecDeque<ElementIndex>,
+    },
+    Leaf {
+        value: LT,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct ArenaEntry<IT, LT> {
+    parent: Option<ElementIndex>,
+    data: ArenaData<IT, LT>,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<IT, LT> {
+    generation: u64,
+    entry: Option<ArenaEntry<IT, LT>>,
+}
+
+// The elements of an ArenaTree, stored in a single slab
+#[derive(Debug, Clone)]
+struct Arena<IT, LT> {
+    slots: Vec<Slot<IT, LT>>,
+    free_slots: Vec<usize>,
+    root: ElementIndex,
+    len: usize,
+}
+
+type SharedArena<IT, LT> = Rc<RefCell<Arena<IT, LT>>>;
+
+// Tree that stores all of its elements in one arena instead of a heap allocation per element.
+// The handles mirror Node, Leaf and TreeElement. They share the arena, so a value that is
+// borrowed through one handle prevents changes of the whole tree.
+#[derive(Debug)]
+pub struct ArenaTree<IT, LT> {
+    arena: SharedArena<IT, LT>,
+}
+
+// The kinds of elements an ArenaElementType refers to
+mod kind {
+    pub enum Node {}
+    pub enum Leaf {}
+}
+
+// Handle to an element of an ArenaTree. Unlike Node and Leaf, a handle does not keep its
+// element alive: handles to removed elements become stale. Operations that return a Result
+// fail with StaleHandle then, the other operations panic.
+pub struct ArenaElementType<IT, LT, K> {
+    arena: SharedArena<IT, LT>,
+    index: ElementIndex,
+    kind: PhantomData<K>,
+}
+
+pub type ArenaNode<IT, LT> = ArenaElementType<IT, LT, kind::Node>;
+pub type ArenaLeaf<IT, LT> = ArenaElementType<IT, LT, kind::Leaf>;
+
+#[derive(Debug)]
+pub enum ArenaElement<IT, LT> {
+    Node(ArenaNode<IT, LT>),
+    Leaf(ArenaLeaf<IT, LT>),
+}
+
+impl<IT, LT> ArenaData<IT, LT> {
+    fn new(value: Value<IT, LT>) -> Self {
+        match value {
+            Value::Node(value) => ArenaData::Node {
+                value,
+                children: VecDeque::new(),
+            },
+            Value::Leaf(value) => ArenaData::Leaf { value },
+        }
+    }
+}
+
+impl<IT, LT> Arena<IT, LT> {
+    fn new(root_value: Value<IT, LT>) -> Self {
+        let mut arena = Arena::empty();
+        arena.root = arena.allocate(ArenaEntry {
+            parent: None,
+            data: ArenaData::new(root_value),
+        });
+        arena
+    }
+
+    // An arena without elements, all handles to it are stale
+    fn empty() -> Self {
+        Arena {
+            slots: vec![],
+            free_slots: vec![],
+            root: ElementIndex {
+                index: 0,
+                generation: 0,
+            },
+            len: 0,
+        }
+    }
+
+    fn allocate(&mut self, entry: ArenaEntry<IT, LT>) -> ElementIndex {
+        self.len += 1;
+        match self.free_slots.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.entry = Some(entry);
+                ElementIndex {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry: Some(entry),
+                });
+                ElementIndex {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    fn release(&mut self, element: ElementIndex) -> Result<ArenaEntry<IT, LT>, DLTreeError> {
+        self.entry(element)?;
+        let slot = &mut self.slots[element.index];
+        slot.generation += 1;
+        self.free_slots.push(element.index);
+        self.len -= 1;
+        slot.entry.take().ok_or(DLTreeError::StaleHandle)
+    }
+
+    fn entry(&self, element: ElementIndex) -> Result<&ArenaEntry<IT, LT>, DLTreeError> {
+        match self.slots.get(element.index) {
+            Some(slot) if slot.generation == element.generation => {
+                slot.entry.as_ref().ok_or(DLTreeError::StaleHandle)
+            }
+            _ => Err(DLTreeError::StaleHandle),
+        }
+    }
+
+    fn entry_mut(&mut self, element: ElementIndex) -> Result<&mut ArenaEntry<IT, LT>, DLTreeError> {
+        match self.slots.get_mut(element.index) {
+            Some(slot) if slot.generation == element.generation => {
+                slot.entry.as_mut().ok_or(DLTreeError::StaleHandle)
+            }
+            _ => Err(DLTreeError::StaleHandle),
+        }
+    }
+
+    fn children_of(&self, node: ElementIndex) -> Result<&VecDeque<ElementIndex>, DLTreeError> {
+        match &self.entry(node)?.data {
+            ArenaData::Node { children, .. } => Ok(children),
+            ArenaData::Leaf { .. } => Err(DLTreeError::ElementKindMismatch),
+        }
+    }
+
+    fn children_mut(
+        &mut self,
+        node: ElementIndex,
+    ) -> Result<&mut VecDeque<ElementIndex>, DLTreeError> {
+        match &mut self.entry_mut(node)?.data {
+            ArenaData::Node { children, .. } => Ok(children),
+            ArenaData::Leaf { .. } => Err(DLTreeError::ElementKindMismatch),
+        }
+    }
+
+    fn parent(&self, element: ElementIndex) -> Result<Option<ElementIndex>, DLTreeError> {
+        Ok(self.entry(element)?.parent)
+    }
+
+    fn is_node(&self, element: ElementIndex) -> Result<bool, DLTreeError> {
+        Ok(matches!(self.entry(element)?.data, ArenaData::Node { .. }))
+    }
+
+    fn value(&self, element: ElementIndex) -> Result<Value<&IT, &LT>, DLTreeError> {
+        Ok(match &self.entry(element)?.data {
+            ArenaData::Node { value, .. } => Value::Node(value),
+            ArenaData::Leaf { value } => Value::Leaf(value),
+        })
+    }
+
+    // Returns the parent of an element together with the position of the element among its siblings
+    fn position(&self, element: ElementIndex) -> Result<(ElementIndex, usize), DLTreeError> {
+        let parent = self
+            .parent(element)?
+            .ok_or(DLTreeError::ChildOperationOnRootLevel)?;
+        let index = self
+            .children_of(parent)?
+            .iter()
+            .position(|c| *c == element)
+            .ok_or(DLTreeError::IntegrityViolated)?;
+        Ok((parent, index))
+    }
+
+    fn insert_child(
+        &mut self,
+        parent: ElementIndex,
+        index: usize,
+        value: Value<IT, LT>,
+    ) -> Result<ElementIndex, DLTreeError> {
+        self.children_of(parent)?;
+        let child = self.allocate(ArenaEntry {
+            parent: Some(parent),
+            data: ArenaData::new(value),
+        });
+        self.children_mut(parent)?.insert(index, child);
+        Ok(child)
+    }
+
+    fn push_back_child(
+        &mut self,
+        parent: ElementIndex,
+        value: Value<IT, LT>,
+    ) -> Result<ElementIndex, DLTreeError> {
+        let index = self.children_of(parent)?.len();
+        self.insert_child(parent, index, value)
+    }
+
+    // Frees the given element and all of its descendants
+    fn release_subtree(&mut self, element: ElementIndex) -> Result<(), DLTreeError> {
+        let mut stack = vec![element];
+        while let Some(element) = stack.pop() {
+            if let ArenaData::Node { children, .. } = self.release(element)?.data {
+                stack.extend(children);
+            }
+        }
+        Ok(())
+    }
+
+    // Moves the given element and all of its descendants into a new arena
+    fn extract_subtree(&mut self, element: ElementIndex) -> Result<Arena<IT, LT>, DLTreeError> {
+        let mut extracted: Option<Arena<IT, LT>> = None;
+        let mut stack = vec![(element, None)];
+        while let Some((element, new_parent)) = stack.pop() {
+            let (value, children) = match self.release(element)?.data {
+                ArenaData::Node { value, children } => (Value::Node(value), children),
+                ArenaData::Leaf { value } => (Value::Leaf(value), VecDeque::new()),
+            };
+            let new_element = match (&mut extracted, new_parent) {
+                (Some(arena), Some(new_parent)) => arena.push_back_child(new_parent, value)?,
+                (None, _) => {
+                    let arena = Arena::new(value);
+                    let root = arena.root;
+                    extracted = Some(arena);
+                    root
+                }
+                (Some(_), None) => return Err(DLTreeError::IntegrityViolated),
+            };
+            stack.extend(children.into_iter().rev().map(|c| (c, Some(new_element))));
+        }
+        extracted.ok_or(DLTreeError::IntegrityViolated)
+    }
+
+    // Moves all elements of another arena below the given node
+    fn push_child_arena(
+        &mut self,
+        node: ElementIndex,
+        mut subtree: Arena<IT, LT>,
+    ) -> Result<ElementIndex, DLTreeError> {
+        self.children_of(node)?;
+        let mut inserted = None;
+        let mut stack = vec![(subtree.root, node)];
+        while let Some((element, new_parent)) = stack.pop() {
+            let (value, children) = match subtree.release(element)?.data {
+                ArenaData::Node { value, children } => (Value::Node(value), children),
+                ArenaData::Leaf { value } => (Value::Leaf(value), VecDeque::new()),
+            };
+            let new_element = self.push_back_child(new_parent, value)?;
+            inserted.get_or_insert(new_element);
+            stack.extend(children.into_iter().rev().map(|c| (c, new_element)));
+        }
+        inserted.ok_or(DLTreeError::IntegrityViolated)
+    }
+
+    // Replaces the element together with its descendants
+    fn set(
+        &mut self,
+        element: ElementIndex,
+        value: Value<IT, LT>,
+    ) -> Result<ElementIndex, DLTreeError> {
+        let (parent, index) = self.position(element)?;
+        self.release_subtree(element)?;
+        let new_element = self.allocate(ArenaEntry {
+            parent: Some(parent),
+            data: ArenaData::new(value),
+        });
+        self.children_mut(parent)?[index] = new_element;
+        Ok(new_element)
+    }
+
+    fn remove_from_tree(&mut self, element: ElementIndex) -> Result<Arena<IT, LT>, DLTreeError> {
+        let (parent, index) = self.position(element)?;
+        self.children_mut(parent)?.remove(index);
+        self.extract_subtree(element)
+    }
+}
+
+impl<IT, LT> ArenaTree<IT, LT> {
+    pub fn new(root_value: Value<IT, LT>) -> Self {
+        ArenaTree::from_arena(Arena::new(root_value))
+    }
+
+    fn from_arena(arena: Arena<IT, LT>) -> Self {
+        ArenaTree {
+            arena: Rc::new(RefCell::new(arena)),
+        }
+    }
+
+    pub fn root_node(&self) -> ArenaElement<IT, LT> {
+        let root = self.arena.borrow().root;
+        ArenaElement::new(&self.arena, root)
+            .expect("The root of an arena tree is always part of the arena")
+    }
+
+    pub fn len(&self) -> usize {
+        self.arena.borrow().len
+    }
+
+    // An arena tree always contains at least its root
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<IT: Clone, LT: Clone> ArenaTree<IT, LT> {
+    pub fn from_tree(tree: &Tree<IT, LT>) -> Self {
+        let clone_value = |element: &TreeElementImpl<IT, LT>| match element {
+            TreeElementImpl::Node(n) => Value::Node(n.borrow().value.clone()),
+            TreeElementImpl::Leaf(l) => Value::Leaf(l.borrow().value.clone()),
+        };
+        let mut arena = Arena::new(clone_value(&tree.tree));
+        let mut stack = vec![(tree.tree.clone(), arena.root)];
+        while let Some((element, arena_element)) = stack.pop() {
+            if let TreeElementImpl::Node(n) = element {
+                for child in n.borrow().children.iter() {
+                    let arena_child = arena
+                        .push_back_child(arena_element, clone_value(child))
+                        .expect("Children are only pushed to nodes of the arena");
+                    stack.push((child.clone(), arena_child));
+                }
+            }
+        }
+        ArenaTree::from_arena(arena)
+    }
+
+    pub fn to_tree(&self) -> Tree<IT, LT> {
+        let arena = self.arena.borrow();
+        let clone_value = |element| match arena.value(element) {
+            Ok(Value::Node(n)) => Value::Node(n.clone()),
+            Ok(Value::Leaf(l)) => Value::Leaf(l.clone()),
+            Err(_) => panic!("The arena tree integrity is violated"),
+        };
+        let tree = Tree::new(clone_value(arena.root));
+        let mut stack = vec![(tree.root_node(), arena.root)];
+        while let Some((element, arena_element)) = stack.pop() {
+            if let TreeElement::Node(mut node) = element {
+                for arena_child in arena.children_of(arena_element).into_iter().flatten() {
+                    let child = node.push_back_child(clone_value(*arena_child));
+                    stack.push((child, *arena_child));
+                }
+            }
+        }
+        tree
+    }
+}
+
+impl<IT: Clone, LT: Clone> DeepClone for ArenaTree<IT, LT> {
+    fn deep_clone(&self) -> Self {
+        ArenaTree::from_arena(self.arena.borrow().clone())
+    }
+}
+
+impl<IT, LT, K> ArenaElementType<IT, LT, K> {
+    fn new(arena: &SharedArena<IT, LT>, index: ElementIndex) -> Self {
+        ArenaElementType {
+            arena: arena.clone(),
+            index,
+            kind: PhantomData,
+        }
+    }
+
+    // Returns true if the element has been removed from the arena tree
+    pub fn is_stale(&self) -> bool {
+        self.arena.borrow().entry(self.index).is_err()
+    }
+
+    pub fn parent(&self) -> Result<Option<ArenaNode<IT, LT>>, DLTreeError> {
+        let parent = self.arena.borrow().parent(self.index)?;
+        Ok(parent.map(|p| ArenaNode::new(&self.arena, p)))
+    }
+
+    // Replaces this element together with its descendants. This handle becomes stale.
+    pub fn set(&mut self, value: Value<IT, LT>) -> Result<ArenaElement<IT, LT>, DLTreeError> {
+        let new_element = self.arena.borrow_mut().set(self.index, value)?;
+        ArenaElement::new(&self.arena, new_element)
+    }
+
+    pub fn set_leaf(&mut self, value: LT) -> Result<ArenaLeaf<IT, LT>, DLTreeError> {
+        self.set(Value::Leaf(value))?
+            .as_leaf()
+            .ok_or(DLTreeError::IntegrityViolated)
+    }
+
+    pub fn set_node(&mut self, value: IT) -> Result<ArenaNode<IT, LT>, DLTreeError> {
+        self.set(Value::Node(value))?
+            .as_node()
+            .ok_or(DLTreeError::IntegrityViolated)
+    }
+
+    pub fn insert_before(
+        &mut self,
+        value: Value<IT, LT>,
+    ) -> Result<ArenaElement<IT, LT>, DLTreeError> {
+        let inserted = {
+            let mut arena = self.arena.borrow_mut();
+            let (parent, index) = arena.position(self.index)?;
+            arena.insert_child(parent, index, value)?
+        };
+        ArenaElement::new(&self.arena, inserted)
+    }
+
+    pub fn insert_after(
+        &mut self,
+        value: Value<IT, LT>,
+    ) -> Result<ArenaElement<IT, LT>, DLTreeError> {
+        let inserted = {
+            let mut arena = self.arena.borrow_mut();
+            let (parent, index) = arena.position(self.index)?;
+            arena.insert_child(parent, index + 1, value)?
+        };
+        ArenaElement::new(&self.arena, inserted)
+    }
+
+    // Moves this element together with its descendants into a new arena tree.
+    // All handles to the removed elements become stale.
+    pub fn remove_from_tree(&mut self) -> Result<ArenaTree<IT, LT>, DLTreeError> {
+        let removed = self.arena.borrow_mut().remove_from_tree(self.index)?;
+        Ok(ArenaTree::from_arena(removed))
+    }
+}
+
+impl<IT, LT, K> Clone for ArenaElementType<IT, LT, K> {
+    fn clone(&self) -> Self {
+        ArenaElementType::new(&self.arena, self.index)
+    }
+}
+
+impl<IT, LT, K> PartialEq for ArenaElementType<IT, LT, K> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.arena, &other.arena) && self.index == other.index
+    }
+}
+
+impl<IT, LT, K> fmt::Debug for ArenaElementType<IT, LT, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaElementType")
+            .field("index", &self.index.index)
+            .field("generation", &self.index.generation)
+            .finish()
+    }
+}
+
+impl<IT, LT> ArenaLeaf<IT, LT> {
+    pub fn value(&self) -> Ref<'_, LT> {
+        Ref::map(self.arena.borrow(), |a| match a.entry(self.index) {
+            Ok(ArenaEntry {
+                data: ArenaData::Leaf { value },
+                ..
+            }) => value,
+            _ => panic!("The leaf has been removed from the arena tree"),
+        })
+    }
+
+    pub fn value_mut(&self) -> RefMut<'_, LT> {
+        RefMut::map(self.arena.borrow_mut(), |a| match a.entry_mut(self.index) {
+            Ok(ArenaEntry {
+                data: ArenaData::Leaf { value },
+                ..
+            }) => value,
+            _ => panic!("The leaf has been removed from the arena tree"),
+        })
+    }
+}
+
+impl<IT, LT> ArenaNode<IT, LT> {
+    fn expect_children(&self) -> Vec<ElementIndex> {
+        self.arena
+            .borrow()
+            .children_of(self.index)
+            .expect("The node has been removed from the arena tree")
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    pub fn value(&self) -> Ref<'_, IT> {
+        Ref::map(self.arena.borrow(), |a| match a.entry(self.index) {
+            Ok(ArenaEntry {
+                data: ArenaData::Node { value, .. },
+                ..
+            }) => value,
+            _ => panic!("The node has been removed from the arena tree"),
+        })
+    }
+
+    pub fn value_mut(&self) -> RefMut<'_, IT> {
+        RefMut::map(self.arena.borrow_mut(), |a| match a.entry_mut(self.index) {
+            Ok(ArenaEntry {
+                data: ArenaData::Node { value, .. },
+                ..
+            }) => value,
+            _ => panic!("The node has been removed from the arena tree"),
+        })
+    }
+
+    pub fn children(&self) -> Vec<ArenaElement<IT, LT>> {
+        self.expect_children()
+            .into_iter()
+            .map(|c| {
+                ArenaElement::new(&self.arena, c)
+                    .expect("The children of a node are part of the arena")
+            })
+            .collect()
+    }
+
+    fn insert_child(&mut self, index: usize, value: Value<IT, LT>) -> ArenaElement<IT, LT> {
+        let child = self
+            .arena
+            .borrow_mut()
+            .insert_child(self.index, index, value)
+            .expect("The node has been removed from the arena tree");
+        ArenaElement::new(&self.arena, child).expect("The inserted child is part of the arena")
+    }
+
+    pub fn push_back_child(&mut self, value: Value<IT, LT>) -> ArenaElement<IT, LT> {
+        self.insert_child(self.expect_children().len(), value)
+    }
+
+    pub fn push_front_child(&mut self, value: Value<IT, LT>) -> ArenaElement<IT, LT> {
+        self.insert_child(0, value)
+    }
+
+    // Moves all elements of another arena tree below this node. Handles to the elements of the
+    // other arena tree become stale.
+    pub fn push_child_tree(&mut self, subtree: ArenaTree<IT, LT>) -> ArenaElement<IT, LT> {
+        let subtree = subtree.arena.replace(Arena::empty());
+        let child = self
+            .arena
+            .borrow_mut()
+            .push_child_arena(self.index, subtree)
+            .expect("The node has been removed from the arena tree");
+        ArenaElement::new(&self.arena, child).expect("The pushed child is part of the arena")
+    }
+
+    pub fn remove_all_children(&mut self) -> Result<(), DLTreeError> {
+        let mut arena = self.arena.borrow_mut();
+        let children = std::mem::take(arena.children_mut(self.index)?);
+        for child in children {
+            arena.release_subtree(child)?;
+        }
+        Ok(())
+    }
+
+    // The arena is not borrowed while compare runs, so comparators can read the values
+    pub fn sort_children_unstable<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&ArenaElement<IT, LT>, &ArenaElement<IT, LT>) -> Ordering,
+    {
+        let mut children = self.children();
+        children.sort_unstable_by(&mut compare);
+        *self
+            .arena
+            .borrow_mut()
+            .children_mut(self.index)
+            .expect("The node has been removed from the arena tree") =
+            children.iter().map(ArenaElement::index).collect();
+    }
+}
+
+impl<IT, LT> ArenaElement<IT, LT> {
+    fn new(arena: &SharedArena<IT, LT>, index: ElementIndex) -> Result<Self, DLTreeError> {
+        Ok(if arena.borrow().is_node(index)? {
+            ArenaElement::Node(ArenaNode::new(arena, index))
+        } else {
+            ArenaElement::Leaf(ArenaLeaf::new(arena, index))
+        })
+    }
+    fn index(&self) -> ElementIndex {
+        match self {
+            ArenaElement::Node(n) => n.index,
+            ArenaElement::Leaf(l) => l.index,
+        }
+    }
+    pub fn as_node(&self) -> Option<ArenaNode<IT, LT>> {
+        match self {
+            ArenaElement::Node(n) => Some(n.clone()),
+            ArenaElement::Leaf(_) => None,
+        }
+    }
+    pub fn as_leaf(&self) -> Option<ArenaLeaf<IT, LT>> {
+        match self {
+            ArenaElement::Node(_) => None,
+            ArenaElement::Leaf(l) => Some(l.clone()),
+        }
+    }
+    pub fn is_stale(&self) -> bool {
+        match self {
+            ArenaElement::Node(n) => n.is_stale(),
+            ArenaElement::Leaf(l) => l.is_stale(),
+        }
+    }
+    pub fn parent(&self) -> Result<Option<ArenaNode<IT, LT>>, DLTreeError> {
+        match self {
+            ArenaElement::Node(n) => n.parent(),
+            ArenaElement::Leaf(l) => l.parent(),
+        }
+    }
+    pub fn remove_from_tree(&mut self) -> Result<ArenaTree<IT, LT>, DLTreeError> {
+        match self {
+            ArenaElement::Node(n) => n.remove_from_tree(),
+            ArenaElement::Leaf(l) => l.remove_from_tree(),
+        }
+    }
+    pub fn set(&mut self, value: Value<IT, LT>) -> Result<ArenaElement<IT, LT>, DLTreeError> {
+        match self {
+            ArenaElement::Node(n) => n.set(value),
+            ArenaElement::Leaf(l) => l.set(value),
+        }
+    }
+    pub fn set_leaf(&mut self, value: LT) -> Result<ArenaLeaf<IT, LT>, DLTreeError> {
+        match self {
+            ArenaElement::Node(n) => n.set_leaf(value),
+            ArenaElement::Leaf(l) => l.set_leaf(value),
+        }
+    }
+    pub fn set_node(&mut self, value: IT) -> Result<ArenaNode<IT, LT>, DLTreeError> {
+        match self {
+            ArenaElement::Node(n) => n.set_node(value),
+            ArenaElement::Leaf(l) => l.set_node(value),
+        }
+    }
+}
+
+impl<IT, LT> Clone for ArenaElement<IT, LT> {
+    fn clone(&self) -> Self {
+        match self {
+            ArenaElement::Node(n) => ArenaElement::Node(n.clone()),
+            ArenaElement::Leaf(l) => ArenaElement::Leaf(l.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arena::{ArenaElement, ArenaTree};
+    use crate::test_util::describe;
+    use crate::tree::{DLTreeError, Tree, Value};
+    use crate::DeepClone;
+
+    #[test]
+    fn arena_building_test() -> Result<(), DLTreeError> {
+        let tree = ArenaTree::new(Value::Node(1));
+        let mut root = tree.root_node().as_node().unwrap();
+        let mut leaf2 = root.push_back_child(Value::Leaf(2)).as_leaf().unwrap();
+        let mut node3 = root.push_back_child(Value::Node(3)).as_node().unwrap();
+        node3.push_front_child(Value::Leaf(4));
+        leaf2.insert_before(Value::Leaf(5))?;
+        leaf2.insert_after(Value::Node(6))?;
+        assert_eq!(describe(&tree.root_node()), "1(5,2,6(),3(4))");
+        assert_eq!(tree.len(), 6);
+        assert_eq!(node3.parent()?, Some(root.clone()));
+        assert!(matches!(
+            root.insert_before(Value::Leaf(7)),
+            Err(DLTreeError::ChildOperationOnRootLevel)
+        ));
+
+        *node3.value_mut() = 30;
+        *leaf2.value_mut() = 20;
+        root.sort_children_unstable(|a, b| match (a, b) {
+            (ArenaElement::Node(a), ArenaElement::Node(b)) => b.value().cmp(&a.value()),
+            (ArenaElement::Leaf(a), ArenaElement::Leaf(b)) => b.value().cmp(&a.value()),
+            (ArenaElement::Node(_), ArenaElement::Leaf(_)) => std::cmp::Ordering::Less,
+            (ArenaElement::Leaf(_), ArenaElement::Node(_)) => std::cmp::Ordering::Greater,
+        });
+        assert_eq!(describe(&tree.root_node()), "1(30(4),6(),20,5)");
+        Ok(())
+    }
+
+    #[test]
+    fn stale_handle_test() -> Result<(), DLTreeError> {
+        let tree = ArenaTree::new(Value::Node(1));
+        let mut root = tree.root_node().as_node().unwrap();
+        let mut node2 = root.push_back_child(Value::Node(2)).as_node().unwrap();
+        let leaf3 = node2.push_back_child(Value::Leaf(3));
+        let mut leaf4 = root.push_back_child(Value::Leaf(4));
+
+        let removed = node2.remove_from_tree()?;
+        assert_eq!(describe(&removed.root_node()), "2(3)");
+        assert!(node2.is_stale());
+        assert!(matches!(leaf3.parent(), Err(DLTreeError::StaleHandle)));
+        // The freed slots are reused, but the old handles stay stale
+        let leaf5 = root.push_back_child(Value::Leaf(5));
+        let leaf6 = root.push_back_child(Value::Leaf(6));
+        assert!(node2.is_stale() && leaf3.is_stale());
+        assert_eq!(tree.len(), 4);
+
+        let mut node7 = leaf4.set_node(7)?;
+        assert!(leaf4.is_stale());
+        node7.push_child_tree(removed);
+        assert_eq!(describe(&tree.root_node()), "1(7(2(3)),5,6)");
+        root.remove_all_children()?;
+        assert!(leaf5.is_stale() && leaf6.is_stale() && node7.is_stale());
+        assert_eq!(tree.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn tree_conversion_test() {
+        let tree: Tree<i32, i32> = crate::tree!(node 1 => [leaf 2, node 3 => [leaf 4, node 5]]);
+        let arena = ArenaTree::from_tree(&tree);
+        assert_eq!(describe(&arena.root_node()), "1(2,3(4,5()))");
+        let copy = arena.deep_clone();
+        copy.root_node()
+            .as_node()
+            .unwrap()
+            .push_back_child(Value::Leaf(6));
+        assert_eq!(describe(&arena.root_node()), "1(2,3(4,5()))");
+        let converted = arena.to_tree();
+        assert_eq!(
+            ArenaTree::from_tree(&converted).to_tree().to_parent_table(),
+            tree.to_parent_table()
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{Tree, Value};
+use crate::tree_elements::tree_element_type::Node;
+
+// Builds a new tree
+pub struct TreeBuilder<IT, LT> {
+    root: NodeBuilder<IT, LT>,
+}
+
+// Appends elements to the children of an existing node, which may be part of a bigger tree
+pub struct NodeBuilder<IT, LT> {
+    node: Node<IT, LT>,
+}
+
+impl<IT, LT> TreeBuilder<IT, LT> {
+    pub fn new(root_value: IT) -> Self {
+        let tree = Tree::new(Value::Node(root_value));
+        TreeBuilder {
+            root: NodeBuilder::append_to(
+                tree.root_node()
+                    .as_node()
+                    .expect("The root of a tree created from a node value must be a node"),
+            ),
+        }
+    }
+
+    pub fn leaf(mut self, value: LT) -> Self {
+        self.root = self.root.leaf(value);
+        self
+    }
+
+    pub fn node<F>(mut self, value: IT, build_children: F) -> Self
+    where
+        F: FnOnce(NodeBuilder<IT, LT>) -> NodeBuilder<IT, LT>,
+    {
+        self.root = self.root.node(value, build_children);
+        self
+    }
+
+    pub fn current_node(&self) -> Node<IT, LT> {
+        self.root.current_node()
+    }
+
+    pub fn build(self) -> Tree<IT, LT> {
+        Tree {
+            tree: TreeElementImpl::Node(self.root.build().element_impl),
+        }
+    }
+}
+
+impl<IT, LT> NodeBuilder<IT, LT> {
+    pub fn append_to(node: Node<IT, LT>) -> Self {
+        NodeBuilder { node }
+    }
+
+    pub fn leaf(mut self, value: LT) -> Self {
+        self.node.push_back_child(Value::Leaf(value));
+        self
+    }
+
+    pub fn node<F>(mut self, value: IT, build_children: F) -> Self
+    where
+        F: FnOnce(NodeBuilder<IT, LT>) -> NodeBuilder<IT, LT>,
+    {
+        let child = self
+            .node
+            .push_back_child(Value::Node(value))
+            .as_node()
+            .expect("A child pushed as node value must be a node");
+        build_children(NodeBuilder::append_to(child));
+        self
+    }
+
+    pub fn current_node(&self) -> Node<IT, LT> {
+        self.node.clone()
+    }
+
+    // Returns the node the elements have been appended to
+    pub fn build(self) -> Node<IT, LT> {
+        self.node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::{NodeBuilder, TreeBuilder};
+    use crate::test_util::describe;
+    use crate::tree::{DLTreeError, Tree};
+
+    #[test]
+    fn tree_macro_test() {
+        let tree: Tree<i32, i32> = crate::tree!(node 1 => [leaf 2, node 3 => [leaf 4], node 5]);
+        assert_eq!(describe(&tree.root_node()), "1(2,3(4),5())");
+
+        let nested: Tree<i32, i32> = crate::tree!(node 1 => [
+            node 2 => [
+                node 3 => [leaf 4 + 1, leaf 6,],
+            ],
+            leaf 7,
+        ]);
+        assert_eq!(describe(&nested.root_node()), "1(2(3(5,6)),7)");
+        assert_eq!(
+            *nested.root_node().as_node().unwrap().children()[0]
+                .as_node()
+                .unwrap()
+                .children()[0]
+                .parent()
+                .unwrap()
+                .unwrap()
+                .value(),
+            2
+        );
+
+        let leaf: Tree<i32, i32> = crate::tree!(leaf 8);
+        assert_eq!(describe(&leaf.root_node()), "8");
+        let empty_node: Tree<i32, i32> = crate::tree!(node 9);
+        assert_eq!(describe(&empty_node.root_node()), "9()");
+    }
+
+    #[test]
+    fn tree_builder_test() -> Result<(), DLTreeError> {
+        let tree = TreeBuilder::new(1)
+            .leaf(2)
+            .node(3, |b| b.leaf(4).node(5, |b| b))
+            .leaf(6)
+            .build();
+        assert_eq!(describe(&tree.root_node()), "1(2,3(4,5()),6)");
+        let node5 = tree.root_node().as_node().unwrap().children()[1]
+            .as_node()
+            .unwrap()
+            .children()[1]
+            .as_node()
+            .unwrap();
+        assert_eq!(*node5.parent()?.unwrap().value(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn tree_builder_append_test() -> Result<(), DLTreeError> {
+        let tree: Tree<i32, i32> = crate::tree!(node 1 => [leaf 2, node 3]);
+        let node3 = tree.root_node().as_node().unwrap().children()[1]
+            .as_node()
+            .unwrap();
+        let node = NodeBuilder::append_to(node3.clone())
+            .leaf(4)
+            .node(5, |b| b.leaf(6))
+            .build();
+        assert_eq!(describe(&tree.root_node()), "1(2,3(4,5(6)))");
+        assert_eq!(node, node3);
+        assert_eq!(*node.parent()?.unwrap().value(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,373 @@
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::internal::TreeElementTrait;
+use crate::tree::{DLTreeError, Tree, Value};
+use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::tree_element_type::{Node, TreeElementType};
+use std::ops::{Deref, DerefMut};
+
+// Moves through a tree without collecting the children of every visited node.
+// The position among the siblings is cached and only searched again if the tree
+// has been changed in between.
+#[derive(Debug)]
+pub struct TreeCursor<IT, LT> {
+    current: TreeElementImpl<IT, LT>,
+    sibling_index: Option<usize>,
+}
+
+// The parent of the current element and the position of the element among its children
+type Location<IT, LT> = (Node<IT, LT>, usize);
+
+// A cursor that can also edit the tree at its position
+#[derive(Debug)]
+pub struct TreeCursorMut<IT, LT> {
+    cursor: TreeCursor<IT, LT>,
+}
+
+impl<IT, LT> TreeCursor<IT, LT> {
+    pub fn new(element: &TreeElement<IT, LT>) -> Self {
+        TreeCursor {
+            current: element.to_impl(),
+            sibling_index: None,
+        }
+    }
+
+    pub fn current(&self) -> TreeElement<IT, LT> {
+        TreeElement::new(&self.current)
+    }
+
+    fn parent(&self) -> Result<Option<Node<IT, LT>>, DLTreeError> {
+        self.current().parent()
+    }
+
+    fn locate(&mut self) -> Result<Option<Location<IT, LT>>, DLTreeError> {
+        let parent = match self.parent()? {
+            None => return Ok(None),
+            Some(parent) => parent,
+        };
+        let index = {
+            let node = parent.element_impl.borrow();
+            match self.sibling_index {
+                Some(index)
+                    if node
+                        .children
+                        .get(index)
+                        .map_or(false, |c| c.ptr_eq(&self.current)) =>
+                {
+                    index
+                }
+                _ => node
+                    .children
+                    .iter()
+                    .position(|c| c.ptr_eq(&self.current))
+                    .ok_or(DLTreeError::IntegrityViolated)?,
+            }
+        };
+        self.sibling_index = Some(index);
+        Ok(Some((parent, index)))
+    }
+
+    // Position among the children of the parent, None at the root
+    pub fn sibling_index(&mut self) -> Result<Option<usize>, DLTreeError> {
+        Ok(self.locate()?.map(|(_, index)| index))
+    }
+
+    pub fn is_root(&self) -> Result<bool, DLTreeError> {
+        Ok(self.parent()?.is_none())
+    }
+
+    // Moves to the parent. Returns false at the root.
+    pub fn up(&mut self) -> Result<bool, DLTreeError> {
+        match self.parent()? {
+            None => Ok(false),
+            Some(parent) => {
+                self.current = parent.to_impl();
+                self.sibling_index = None;
+                Ok(true)
+            }
+        }
+    }
+
+    // Moves to the child at index. Returns false for leaves and missing children.
+    pub fn down(&mut self, index: usize) -> bool {
+        let child = match &self.current {
+            TreeElementImpl::Node(n) => n.borrow().children.get(index).cloned(),
+            TreeElementImpl::Leaf(_) => None,
+        };
+        match child {
+            None => false,
+            Some(child) => {
+                self.current = child;
+                self.sibling_index = Some(index);
+                true
+            }
+        }
+    }
+
+    fn move_to_sibling(&mut self, forward: bool) -> Result<bool, DLTreeError> {
+        let (parent, index) = match self.locate()? {
+            None => return Ok(false),
+            Some(location) => location,
+        };
+        let sibling_index = if forward {
+            index + 1
+        } else if index > 0 {
+            index - 1
+        } else {
+            return Ok(false);
+        };
+        let sibling = parent
+            .element_impl
+            .borrow()
+            .children
+            .get(sibling_index)
+            .cloned();
+        match sibling {
+            None => Ok(false),
+            Some(sibling) => {
+                self.current = sibling;
+                self.sibling_index = Some(sibling_index);
+                Ok(true)
+            }
+        }
+    }
+
+    // Moves to the next sibling. Returns false at the last child and at the root.
+    // The cursor moves in place and can fail, so it does not implement Iterator.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<bool, DLTreeError> {
+        self.move_to_sibling(true)
+    }
+
+    // Moves to the previous sibling. Returns false at the first child and at the root.
+    pub fn prev(&mut self) -> Result<bool, DLTreeError> {
+        self.move_to_sibling(false)
+    }
+}
+
+impl<IT, LT> Clone for TreeCursor<IT, LT> {
+    fn clone(&self) -> Self {
+        TreeCursor {
+            current: self.current.clone(),
+            sibling_index: self.sibling_index,
+        }
+    }
+}
+
+impl<IT, LT> TreeCursorMut<IT, LT> {
+    pub fn new(element: &TreeElement<IT, LT>) -> Self {
+        TreeCursorMut {
+            cursor: TreeCursor::new(element),
+        }
+    }
+
+    fn current_node(&self) -> Result<Node<IT, LT>, DLTreeError> {
+        match &self.cursor.current {
+            TreeElementImpl::Node(n) => Ok(Node::new(n.clone())),
+            TreeElementImpl::Leaf(_) => Err(DLTreeError::ElementKindMismatch),
+        }
+    }
+
+    fn insert_sibling(
+        &mut self,
+        value: Value<IT, LT>,
+        after: bool,
+    ) -> Result<TreeElement<IT, LT>, DLTreeError> {
+        let (mut parent, index) = self
+            .cursor
+            .locate()?
+            .ok_or(DLTreeError::ChildOperationOnRootLevel)?;
+        let inserted = TreeElementImpl::new(value, None);
+        let insert_index = if after { index + 1 } else { index };
+        parent.insert_child_at(insert_index, inserted.clone())?;
+        if !after {
+            self.cursor.sibling_index = Some(index + 1);
+        }
+        Ok(TreeElement::new(&inserted))
+    }
+
+    // The cursor stays at the current element
+    pub fn insert_before(
+        &mut self,
+        value: Value<IT, LT>,
+    ) -> Result<TreeElement<IT, LT>, DLTreeError> {
+        self.insert_sibling(value, false)
+    }
+
+    // The cursor stays at the current element
+    pub fn insert_after(
+        &mut self,
+        value: Value<IT, LT>,
+    ) -> Result<TreeElement<IT, LT>, DLTreeError> {
+        self.insert_sibling(value, true)
+    }
+
+    pub fn insert_child(
+        &mut self,
+        index: usize,
+        value: Value<IT, LT>,
+    ) -> Result<TreeElement<IT, LT>, DLTreeError> {
+        let mut node = self.current_node()?;
+        if index > node.element_impl.borrow().children.len() {
+            return Err(DLTreeError::IndexOutOfRange);
+        }
+        let inserted = TreeElementImpl::new(value, None);
+        node.insert_child_at(index, inserted.clone())?;
+        Ok(TreeElement::new(&inserted))
+    }
+
+    pub fn push_back_child(
+        &mut self,
+        value: Value<IT, LT>,
+    ) -> Result<TreeElement<IT, LT>, DLTreeError> {
+        Ok(self.current_node()?.push_back_child(value))
+    }
+
+    pub fn push_front_child(
+        &mut self,
+        value: Value<IT, LT>,
+    ) -> Result<TreeElement<IT, LT>, DLTreeError> {
+        Ok(self.current_node()?.push_front_child(value))
+    }
+
+    // Replaces the current element. The cursor moves to the new element.
+    // Returns the replaced element.
+    pub fn replace(&mut self, value: Value<IT, LT>) -> Result<Tree<IT, LT>, DLTreeError> {
+        let replaced = self.cursor.current.clone();
+        let replacement = self.current().set(value)?;
+        self.cursor.current = replacement.to_impl();
+        Ok(Tree { tree: replaced })
+    }
+
+    // Removes the current element. The cursor moves to the parent.
+    // Returns the removed element.
+    pub fn remove(&mut self) -> Result<Tree<IT, LT>, DLTreeError> {
+        let (mut parent, index) = self
+            .cursor
+            .locate()?
+            .ok_or(DLTreeError::ChildOperationOnRootLevel)?;
+        let removed = self.cursor.current.clone();
+        parent.remove_child_at(index, &removed)?;
+        self.cursor.current = parent.to_impl();
+        self.cursor.sibling_index = None;
+        Ok(Tree { tree: removed })
+    }
+}
+
+impl<IT, LT> Deref for TreeCursorMut<IT, LT> {
+    type Target = TreeCursor<IT, LT>;
+
+    fn deref(&self) -> &TreeCursor<IT, LT> {
+        &self.cursor
+    }
+}
+
+impl<IT, LT> DerefMut for TreeCursorMut<IT, LT> {
+    fn deref_mut(&mut self) -> &mut TreeCursor<IT, LT> {
+        &mut self.cursor
+    }
+}
+
+impl<IT, LT> TreeElement<IT, LT> {
+    pub fn cursor(&self) -> TreeCursor<IT, LT> {
+        TreeCursor::new(self)
+    }
+
+    pub fn cursor_mut(&self) -> TreeCursorMut<IT, LT> {
+        TreeCursorMut::new(self)
+    }
+}
+
+impl<IT, LT, T: TreeElementTrait<IT, LT>> TreeElementType<IT, LT, T> {
+    pub fn cursor(&self) -> TreeCursor<IT, LT> {
+        TreeCursor {
+            current: self.to_impl(),
+            sibling_index: None,
+        }
+    }
+
+    pub fn cursor_mut(&self) -> TreeCursorMut<IT, LT> {
+        TreeCursorMut {
+            cursor: self.cursor(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{build_number_tree, describe};
+    use crate::tree::{DLTreeError, Value};
+
+    #[test]
+    fn navigation_test() -> Result<(), DLTreeError> {
+        let tree = build_number_tree();
+        let mut cursor = tree.root_node().cursor();
+        assert!(cursor.is_root()?);
+        assert!(!cursor.up()?);
+        assert!(!cursor.next()?);
+        assert_eq!(cursor.sibling_index()?, None);
+        assert!(!cursor.down(3));
+        assert!(cursor.down(1));
+        assert_eq!(*cursor.current().value(), 3);
+        assert!(cursor.down(1));
+        assert!(!cursor.down(0));
+        assert!(!cursor.next()?);
+        assert!(cursor.prev()?);
+        assert!(!cursor.prev()?);
+        assert_eq!(*cursor.current().value(), 4);
+        assert!(cursor.up()?);
+        assert_eq!(cursor.sibling_index()?, Some(1));
+        assert!(cursor.next()?);
+        assert_eq!(*cursor.current().value(), 6);
+
+        // The cached position is searched again after the tree has changed
+        tree.root_node()
+            .as_node()
+            .unwrap()
+            .push_front_child(Value::Leaf(7));
+        assert_eq!(cursor.sibling_index()?, Some(3));
+        assert!(cursor.prev()?);
+        assert_eq!(*cursor.current().value(), 3);
+
+        let leaf = cursor.current().as_node().unwrap().children()[0].clone();
+        let mut leaf_cursor = leaf.as_leaf().unwrap().cursor();
+        assert!(leaf_cursor.up()?);
+        assert_eq!(*leaf_cursor.current().value(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn editing_test() -> Result<(), DLTreeError> {
+        let tree = build_number_tree();
+        let mut cursor = tree.root_node().cursor_mut();
+        assert_eq!(
+            cursor.insert_before(Value::Leaf(0)).unwrap_err(),
+            DLTreeError::ChildOperationOnRootLevel
+        );
+        cursor.down(1);
+        cursor.insert_before(Value::Leaf(7))?;
+        cursor.insert_after(Value::Leaf(8))?;
+        assert_eq!(cursor.sibling_index()?, Some(2));
+        cursor.insert_child(2, Value::Leaf(9))?;
+        assert_eq!(
+            cursor.insert_child(4, Value::Leaf(0)).unwrap_err(),
+            DLTreeError::IndexOutOfRange
+        );
+        cursor.push_front_child(Value::Leaf(10))?;
+        assert_eq!(describe(&tree.root_node()), "1(2,7,3(10,4,5(),9),8,6)");
+
+        cursor.down(2);
+        let replaced = cursor.replace(Value::Leaf(11))?;
+        assert_eq!(describe(&replaced.root_node()), "5()");
+        assert!(replaced.root_node().parent()?.is_none());
+        assert_eq!(
+            cursor.push_back_child(Value::Leaf(0)).unwrap_err(),
+            DLTreeError::ElementKindMismatch
+        );
+        assert!(cursor.prev()?);
+        let removed = cursor.remove()?;
+        assert_eq!(describe(&removed.root_node()), "4");
+        assert_eq!(*cursor.current().value(), 3);
+        assert_eq!(describe(&tree.root_node()), "1(2,7,3(10,11,9),8,6)");
+        Ok(())
+    }
+}
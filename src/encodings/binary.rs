@@ -0,0 +1,489 @@
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{Tree, Value};
+use crate::tree_elements::tree_element_type::Node;
+use std::io::{self, Read, Write};
+
+// Layout of a binary encoded tree:
+//   magic (4 bytes) | version (1 byte) | body | checksum (CRC-32, 4 bytes little endian)
+// The body is a pre-order stream of elements. Every element starts with its kind tag followed by
+// the user encoded payload. Nodes additionally carry their child count as LEB128 varint.
+// The checksum covers the body only.
+const MAGIC: [u8; 4] = *b"DLTR";
+const VERSION: u8 = 1;
+const NODE_TAG: u8 = 0;
+const LEAF_TAG: u8 = 1;
+
+#[derive(Debug)]
+pub enum CodecError {
+    Io(io::Error),
+    // The stream does not start with the dltree magic bytes
+    InvalidMagic,
+    // The stream has been written by an incompatible version of the format
+    UnsupportedVersion(u8),
+    // An element kind tag is neither a node nor a leaf
+    InvalidTag(u8),
+    // The checksum at the end of the stream does not match the decoded body
+    ChecksumMismatch,
+}
+
+impl From<io::Error> for CodecError {
+    fn from(error: io::Error) -> Self {
+        CodecError::Io(error)
+    }
+}
+
+pub trait Encode {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+}
+
+pub trait Decode: Sized {
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! impl_codec_for_number {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+            }
+
+            impl Decode for $t {
+                fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+                    let mut bytes = [0u8; std::mem::size_of::<$t>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(<$t>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_for_number!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl Encode for usize {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_varint(writer, *self as u64)
+    }
+}
+
+impl Decode for usize {
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        read_length(reader)
+    }
+}
+
+impl Encode for bool {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (*self as u8).encode(writer)
+    }
+}
+
+impl Decode for bool {
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid_data("invalid boolean value")),
+        }
+    }
+}
+
+impl Encode for char {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (*self as u32).encode(writer)
+    }
+}
+
+impl Decode for char {
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        std::char::from_u32(u32::decode(reader)?).ok_or_else(|| invalid_data("invalid char value"))
+    }
+}
+
+impl Encode for () {
+    fn encode<W: Write>(&self, _writer: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Decode for () {
+    fn decode<R: Read>(_reader: &mut R) -> io::Result<Self> {
+        Ok(())
+    }
+}
+
+impl Encode for str {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_varint(writer, self.len() as u64)?;
+        writer.write_all(self.as_bytes())
+    }
+}
+
+impl Encode for String {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.as_str().encode(writer)
+    }
+}
+
+impl Decode for String {
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let len = read_length(reader)?;
+        let mut bytes = vec![];
+        reader.take(len as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        String::from_utf8(bytes).map_err(|_| invalid_data("invalid utf-8 string"))
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            None => false.encode(writer),
+            Some(value) => {
+                true.encode(writer)?;
+                value.encode(writer)
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        if bool::decode(reader)? {
+            Ok(Some(T::decode(reader)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_varint(writer, self.len() as u64)?;
+        self.iter().try_for_each(|item| item.encode(writer))
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let len = read_length(reader)?;
+        // The length is untrusted input, so the capacity is only a hint
+        let mut items = Vec::with_capacity(len.min(1024));
+        for _ in 0..len {
+            items.push(T::decode(reader)?);
+        }
+        Ok(items)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return writer.write_all(&[byte]);
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = u8::decode(reader)?;
+        if shift == 63 && byte > 1 {
+            return Err(invalid_data("varint overflow"));
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+        if shift > 63 {
+            return Err(invalid_data("varint overflow"));
+        }
+    }
+}
+
+fn read_length<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let value = read_varint(reader)?;
+    if value > usize::MAX as u64 {
+        return Err(invalid_data("length exceeds the address space"));
+    }
+    Ok(value as usize)
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+struct Crc32 {
+    state: u32,
+}
+
+impl Crc32 {
+    fn new() -> Self {
+        Crc32 { state: 0xffff_ffff }
+    }
+    fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state =
+                CRC32_TABLE[((self.state ^ u32::from(*byte)) & 0xff) as usize] ^ (self.state >> 8);
+        }
+    }
+    fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+struct ChecksumWriter<W> {
+    inner: W,
+    crc: Crc32,
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.crc.update(&buf[..written]);
+        Ok(written)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct ChecksumReader<R> {
+    inner: R,
+    crc: Crc32,
+}
+
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.crc.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<IT: Encode, LT: Encode> Tree<IT, LT> {
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> Result<(), CodecError> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        let mut body = ChecksumWriter {
+            inner: &mut writer,
+            crc: Crc32::new(),
+        };
+        // The stack holds the elements that still have to be written in reversed order
+        // so that deep trees do not exhaust the call stack
+        let mut stack = vec![self.tree.clone()];
+        while let Some(element) = stack.pop() {
+            match element {
+                TreeElementImpl::Node(n) => {
+                    let node = n.borrow();
+                    body.write_all(&[NODE_TAG])?;
+                    node.value.encode(&mut body)?;
+                    write_varint(&mut body, node.children.len() as u64)?;
+                    stack.extend(node.children.iter().rev().cloned());
+                }
+                TreeElementImpl::Leaf(l) => {
+                    body.write_all(&[LEAF_TAG])?;
+                    l.borrow().value.encode(&mut body)?;
+                }
+            }
+        }
+        let checksum = body.crc.finish();
+        writer.write_all(&checksum.to_le_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write_binary(&mut bytes)
+            .expect("Writing into a Vec<u8> must not fail");
+        bytes
+    }
+}
+
+impl<IT: Decode, LT: Decode> Tree<IT, LT> {
+    pub fn read_binary<R: Read>(mut reader: R) -> Result<Self, CodecError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(CodecError::InvalidMagic);
+        }
+        let version = u8::decode(&mut reader)?;
+        if version != VERSION {
+            return Err(CodecError::UnsupportedVersion(version));
+        }
+        let mut body = ChecksumReader {
+            inner: &mut reader,
+            crc: Crc32::new(),
+        };
+
+        let (root_value, root_child_count) = read_element(&mut body)?;
+        let tree = Tree::new(root_value);
+        // Every stack entry is a node together with the number of children that are still to be read
+        let mut stack: Vec<(Node<IT, LT>, usize)> = vec![];
+        if let Some(node) = tree.root_node().as_node() {
+            if root_child_count > 0 {
+                stack.push((node, root_child_count));
+            }
+        }
+        while let Some((node, remaining)) = stack.last_mut() {
+            let (value, child_count) = read_element(&mut body)?;
+            let child = node.push_back_child(value);
+            *remaining -= 1;
+            if *remaining == 0 {
+                stack.pop();
+            }
+            if let Some(child_node) = child.as_node() {
+                if child_count > 0 {
+                    stack.push((child_node, child_count));
+                }
+            }
+        }
+
+        let checksum = body.crc.finish();
+        if u32::decode(&mut reader)? != checksum {
+            return Err(CodecError::ChecksumMismatch);
+        }
+        Ok(tree)
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, CodecError> {
+        Self::read_binary(bytes)
+    }
+}
+
+fn read_element<R: Read, IT: Decode, LT: Decode>(
+    reader: &mut R,
+) -> Result<(Value<IT, LT>, usize), CodecError> {
+    match u8::decode(reader)? {
+        NODE_TAG => {
+            let value = IT::decode(reader)?;
+            let child_count = read_length(reader)?;
+            Ok((Value::Node(value), child_count))
+        }
+        LEAF_TAG => Ok((Value::Leaf(LT::decode(reader)?), 0)),
+        tag => Err(CodecError::InvalidTag(tag)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{build_tree, describe};
+    use crate::tree::{Tree, Value};
+
+    #[test]
+    fn round_trip_test() -> Result<(), CodecError> {
+        let tree = build_tree();
+        let bytes = tree.to_binary();
+        assert_eq!(&bytes[..4], b"DLTR");
+        let decoded = Tree::<String, u32>::from_binary(&bytes)?;
+        assert_eq!(describe(&decoded.root_node()), "a(1,b(2,c(3)),d(4,e()),5)");
+        assert_eq!(describe(&decoded.root_node()), describe(&tree.root_node()));
+
+        let leaf_tree = Tree::<String, u32>::new(Value::Leaf(42));
+        let decoded_leaf = Tree::<String, u32>::from_binary(&leaf_tree.to_binary())?;
+        assert_eq!(*decoded_leaf.root_node().as_leaf().unwrap().value(), 42);
+        Ok(())
+    }
+
+    #[test]
+    fn streaming_test() -> Result<(), CodecError> {
+        let mut stream = io::Cursor::new(vec![]);
+        build_tree().write_binary(&mut stream)?;
+        stream.write_all(b"trailing data")?;
+        stream.set_position(0);
+        let decoded = Tree::<String, u32>::read_binary(&mut stream)?;
+        assert_eq!(describe(&decoded.root_node()), "a(1,b(2,c(3)),d(4,e()),5)");
+        let mut rest = String::new();
+        stream.read_to_string(&mut rest)?;
+        assert_eq!(rest, "trailing data");
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted_data_test() {
+        let bytes = build_tree().to_binary();
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(matches!(
+            Tree::<String, u32>::from_binary(&wrong_magic),
+            Err(CodecError::InvalidMagic)
+        ));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = VERSION + 1;
+        assert!(matches!(
+            Tree::<String, u32>::from_binary(&wrong_version),
+            Err(CodecError::UnsupportedVersion(_))
+        ));
+
+        let mut flipped_payload = bytes.clone();
+        let last_leaf = bytes.len() - 8;
+        flipped_payload[last_leaf] ^= 0x01;
+        assert!(matches!(
+            Tree::<String, u32>::from_binary(&flipped_payload),
+            Err(CodecError::ChecksumMismatch)
+        ));
+
+        let mut wrong_tag = bytes.clone();
+        wrong_tag[5] = 7;
+        assert!(matches!(
+            Tree::<String, u32>::from_binary(&wrong_tag),
+            Err(CodecError::InvalidTag(7))
+        ));
+
+        assert!(matches!(
+            Tree::<String, u32>::from_binary(&bytes[..bytes.len() - 1]),
+            Err(CodecError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn payload_codec_test() -> io::Result<()> {
+        let mut bytes = vec![];
+        (-5i64).encode(&mut bytes)?;
+        300usize.encode(&mut bytes)?;
+        Some('ä').encode(&mut bytes)?;
+        vec![true, false].encode(&mut bytes)?;
+        let mut reader = bytes.as_slice();
+        assert_eq!(i64::decode(&mut reader)?, -5);
+        assert_eq!(usize::decode(&mut reader)?, 300);
+        assert_eq!(Option::<char>::decode(&mut reader)?, Some('ä'));
+        assert_eq!(Vec::<bool>::decode(&mut reader)?, vec![true, false]);
+        assert!(reader.is_empty());
+        Ok(())
+    }
+}
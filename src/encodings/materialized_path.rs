@@ -0,0 +1,194 @@
+use crate::encodings::parent_table::ParentTableRow;
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{DLTreeError, Tree, Value};
+
+const SEPARATOR: char = '/';
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterializedPathRow<IT, LT> {
+    pub path: String,
+    pub value: Value<IT, LT>,
+}
+
+impl<IT: Clone, LT: Clone> Tree<IT, LT> {
+    // Emits one row per element in pre-order.
+    // Every path segment is the 1-based position of the element among its siblings, e.g. "1/4/2".
+    pub fn to_materialized_paths(&self) -> Vec<MaterializedPathRow<IT, LT>> {
+        let mut rows = vec![];
+        let mut stack = vec![(self.tree.clone(), "1".to_string())];
+        while let Some((element, path)) = stack.pop() {
+            match element {
+                TreeElementImpl::Node(n) => {
+                    let node = n.borrow();
+                    stack.extend(
+                        node.children
+                            .iter()
+                            .enumerate()
+                            .rev()
+                            .map(|(i, c)| (c.clone(), format!("{}{}{}", path, SEPARATOR, i + 1))),
+                    );
+                    rows.push(MaterializedPathRow {
+                        path,
+                        value: Value::Node(node.value.clone()),
+                    });
+                }
+                TreeElementImpl::Leaf(l) => rows.push(MaterializedPathRow {
+                    path,
+                    value: Value::Leaf(l.borrow().value.clone()),
+                }),
+            }
+        }
+        rows
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    // Reconstructs a tree from rows in arbitrary order.
+    // Siblings are ordered by the number in the last segment of their paths. Other segments are
+    // treated as opaque ids, siblings with such segments come first and keep the relative order
+    // of their rows.
+    pub fn from_materialized_paths<I>(rows: I) -> Result<Tree<IT, LT>, DLTreeError>
+    where
+        I: IntoIterator<Item = MaterializedPathRow<IT, LT>>,
+    {
+        let mut rows: Vec<MaterializedPathRow<IT, LT>> = rows.into_iter().collect();
+        // The sort is stable, so only siblings with numbered segments change their order
+        rows.sort_by_key(|row| {
+            row.path
+                .rsplit(SEPARATOR)
+                .next()
+                .and_then(|segment| segment.parse::<u64>().ok())
+        });
+        let parent_table = rows
+            .into_iter()
+            .map(|row| {
+                if row.path.split(SEPARATOR).any(|segment| segment.is_empty()) {
+                    return Err(DLTreeError::MalformedEncoding);
+                }
+                let parent_id = row
+                    .path
+                    .rfind(SEPARATOR)
+                    .map(|position| row.path[..position].to_string());
+                Ok(ParentTableRow {
+                    id: row.path,
+                    parent_id,
+                    value: row.value,
+                })
+            })
+            .collect::<Result<Vec<_>, DLTreeError>>()?;
+        Tree::from_parent_table(parent_table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encodings::materialized_path::MaterializedPathRow;
+    use crate::tree::{DLTreeError, Tree, Value};
+
+    fn row(path: &str, value: Value<char, u32>) -> MaterializedPathRow<char, u32> {
+        MaterializedPathRow {
+            path: path.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn round_trip_test() -> Result<(), DLTreeError> {
+        let tree: Tree<char, u32> =
+            crate::tree!(node 'a' => [leaf 1, node 'b' => [leaf 2, node 'c'], leaf 3]);
+        let rows = tree.to_materialized_paths();
+        assert_eq!(
+            rows,
+            vec![
+                row("1", Value::Node('a')),
+                row("1/1", Value::Leaf(1)),
+                row("1/2", Value::Node('b')),
+                row("1/2/1", Value::Leaf(2)),
+                row("1/2/2", Value::Node('c')),
+                row("1/3", Value::Leaf(3)),
+            ]
+        );
+
+        // Parents after children, siblings keep their relative order
+        let mut shuffled = rows.clone();
+        shuffled.sort_by_key(|r| std::cmp::Reverse(r.path.matches('/').count()));
+        let rebuilt = Tree::from_materialized_paths(shuffled)?;
+        assert_eq!(rebuilt.to_materialized_paths(), rows);
+        assert_eq!(rebuilt.to_parent_table(), tree.to_parent_table());
+        Ok(())
+    }
+
+    #[test]
+    fn numeric_order_test() -> Result<(), DLTreeError> {
+        let mut tree: Tree<char, u32> = Tree::new(Value::Node('a'));
+        let mut root = tree.root_node().as_node().unwrap();
+        for value in 0..11 {
+            root.push_back_child(Value::Leaf(value));
+        }
+        let rows = tree.to_materialized_paths();
+        // Sorted like the rows of a database table, "1/10" before "1/2"
+        let mut sorted = rows.clone();
+        sorted.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(sorted[2].path, "1/10");
+        tree = Tree::from_materialized_paths(sorted)?;
+        assert_eq!(tree.to_materialized_paths(), rows);
+        Ok(())
+    }
+
+    #[test]
+    fn opaque_segments_test() -> Result<(), DLTreeError> {
+        let rows = vec![
+            row("root", Value::Node('r')),
+            row("root/x", Value::Node('x')),
+            row("root/x/z", Value::Leaf(2)),
+            row("root/y", Value::Leaf(1)),
+        ];
+        let tree = Tree::from_materialized_paths(rows)?;
+        let paths = tree
+            .to_materialized_paths()
+            .into_iter()
+            .map(|r| r.path)
+            .collect::<Vec<String>>();
+        assert_eq!(paths, vec!["1", "1/1", "1/1/1", "1/2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_paths_test() {
+        assert!(matches!(
+            Tree::from_materialized_paths(vec![
+                row("1", Value::Node('a')),
+                row("1//2", Value::Leaf(1))
+            ]),
+            Err(DLTreeError::MalformedEncoding)
+        ));
+        assert!(matches!(
+            Tree::from_materialized_paths(vec![
+                row("1", Value::Node('a')),
+                row("2/1", Value::Leaf(1))
+            ]),
+            Err(DLTreeError::MissingParent)
+        ));
+        assert!(matches!(
+            Tree::from_materialized_paths(vec![
+                row("1", Value::Node('a')),
+                row("1", Value::Leaf(1))
+            ]),
+            Err(DLTreeError::DuplicateId)
+        ));
+        assert!(matches!(
+            Tree::from_materialized_paths(vec![
+                row("1", Value::Node('a')),
+                row("2", Value::Leaf(1))
+            ]),
+            Err(DLTreeError::MultipleRoots)
+        ));
+        assert!(matches!(
+            Tree::from_materialized_paths(vec![
+                row("1", Value::Leaf(1)),
+                row("1/1", Value::Leaf(1))
+            ]),
+            Err(DLTreeError::LeafWithChildren)
+        ));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod binary;
+pub mod materialized_path;
+pub mod nested_set;
+pub mod parent_table;
@@ -0,0 +1,165 @@
+use crate::encodings::parent_table::ParentTableRow;
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{DLTreeError, Tree, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedSetRow<IT, LT> {
+    pub left: usize,
+    pub right: usize,
+    pub value: Value<IT, LT>,
+}
+
+enum Step<IT, LT> {
+    Enter(TreeElementImpl<IT, LT>),
+    Leave(usize),
+}
+
+impl<IT: Clone, LT: Clone> Tree<IT, LT> {
+    // Emits one row per element in pre-order. Numbering starts with 1 at the left side of the root.
+    pub fn to_nested_set(&self) -> Vec<NestedSetRow<IT, LT>> {
+        let mut rows = vec![];
+        let mut counter = 1;
+        let mut stack = vec![Step::Enter(self.tree.clone())];
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Enter(TreeElementImpl::Node(n)) => {
+                    let node = n.borrow();
+                    stack.push(Step::Leave(rows.len()));
+                    stack.extend(node.children.iter().rev().map(|c| Step::Enter(c.clone())));
+                    rows.push(NestedSetRow {
+                        left: counter,
+                        right: 0,
+                        value: Value::Node(node.value.clone()),
+                    });
+                    counter += 1;
+                }
+                Step::Enter(TreeElementImpl::Leaf(l)) => {
+                    rows.push(NestedSetRow {
+                        left: counter,
+                        right: counter + 1,
+                        value: Value::Leaf(l.borrow().value.clone()),
+                    });
+                    counter += 2;
+                }
+                Step::Leave(index) => {
+                    rows[index].right = counter;
+                    counter += 1;
+                }
+            }
+        }
+        rows
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    // Reconstructs a tree from rows in arbitrary order. Gaps in the numbering are allowed.
+    pub fn from_nested_set<I>(rows: I) -> Result<Tree<IT, LT>, DLTreeError>
+    where
+        I: IntoIterator<Item = NestedSetRow<IT, LT>>,
+    {
+        let mut rows: Vec<NestedSetRow<IT, LT>> = rows.into_iter().collect();
+        rows.sort_by_key(|row| row.left);
+
+        // Every stack entry is the (left, right) interval of an ancestor of the current row
+        let mut ancestors: Vec<(usize, usize)> = vec![];
+        let mut previous_left = None;
+        let mut parent_table = Vec::with_capacity(rows.len());
+        for row in rows {
+            if row.left >= row.right {
+                return Err(DLTreeError::MalformedEncoding);
+            }
+            if previous_left.replace(row.left) == Some(row.left) {
+                return Err(DLTreeError::DuplicateId);
+            }
+            while let Some((_, right)) = ancestors.last() {
+                if *right < row.left {
+                    ancestors.pop();
+                } else {
+                    break;
+                }
+            }
+            let parent_id = match ancestors.last() {
+                // Intervals must either be nested or disjoint
+                Some((_, right)) if row.right >= *right => {
+                    return Err(DLTreeError::MalformedEncoding)
+                }
+                Some((left, _)) => Some(*left),
+                None => None,
+            };
+            ancestors.push((row.left, row.right));
+            parent_table.push(ParentTableRow {
+                id: row.left,
+                parent_id,
+                value: row.value,
+            });
+        }
+        Tree::from_parent_table(parent_table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encodings::nested_set::NestedSetRow;
+    use crate::tree::{DLTreeError, Tree, Value};
+
+    fn row(left: usize, right: usize, value: Value<char, u32>) -> NestedSetRow<char, u32> {
+        NestedSetRow { left, right, value }
+    }
+
+    #[test]
+    fn round_trip_test() -> Result<(), DLTreeError> {
+        let tree: Tree<char, u32> =
+            crate::tree!(node 'a' => [leaf 1, node 'b' => [leaf 2, node 'c'], leaf 3]);
+        let rows = tree.to_nested_set();
+        assert_eq!(
+            rows,
+            vec![
+                row(1, 12, Value::Node('a')),
+                row(2, 3, Value::Leaf(1)),
+                row(4, 9, Value::Node('b')),
+                row(5, 6, Value::Leaf(2)),
+                row(7, 8, Value::Node('c')),
+                row(10, 11, Value::Leaf(3)),
+            ]
+        );
+
+        let mut reversed = rows.clone();
+        reversed.reverse();
+        let rebuilt = Tree::from_nested_set(reversed)?;
+        assert_eq!(rebuilt.to_nested_set(), rows);
+        // The empty node 'c' has to stay a node and must not turn into a leaf
+        assert_eq!(rebuilt.to_parent_table(), tree.to_parent_table());
+
+        let leaf = Tree::<char, u32>::new(Value::Leaf(7));
+        assert_eq!(leaf.to_nested_set(), vec![row(1, 2, Value::Leaf(7))]);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_nested_set_test() {
+        assert!(matches!(
+            Tree::from_nested_set(vec![row(1, 4, Value::Node('a')), row(2, 5, Value::Leaf(1))]),
+            Err(DLTreeError::MalformedEncoding)
+        ));
+        assert!(matches!(
+            Tree::from_nested_set(vec![row(3, 3, Value::Leaf(1))]),
+            Err(DLTreeError::MalformedEncoding)
+        ));
+        assert!(matches!(
+            Tree::from_nested_set(vec![row(1, 6, Value::Node('a')), row(1, 2, Value::Leaf(1))]),
+            Err(DLTreeError::DuplicateId)
+        ));
+        assert!(matches!(
+            Tree::from_nested_set(vec![row(1, 2, Value::Node('a')), row(3, 4, Value::Leaf(1))]),
+            Err(DLTreeError::MultipleRoots)
+        ));
+        assert!(matches!(
+            Tree::from_nested_set(vec![row(1, 4, Value::Leaf(1)), row(2, 3, Value::Leaf(2))]),
+            Err(DLTreeError::LeafWithChildren)
+        ));
+        assert!(matches!(
+            Tree::<char, u32>::from_nested_set(vec![]),
+            Err(DLTreeError::MissingRoot)
+        ));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{DLTreeError, Tree, Value};
+use crate::tree_elements::tree_element::TreeElement;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParentTableRow<ID, IT, LT> {
+    pub id: ID,
+    pub parent_id: Option<ID>,
+    pub value: Value<IT, LT>,
+}
+
+impl<IT: Clone, LT: Clone> Tree<IT, LT> {
+    // Emits one row per element in pre-order. The ids are synthetic and equal the row index.
+    pub fn to_parent_table(&self) -> Vec<ParentTableRow<usize, IT, LT>> {
+        let mut rows = vec![];
+        let mut stack = vec![(self.tree.clone(), None)];
+        while let Some((element, parent_id)) = stack.pop() {
+            let id = rows.len();
+            match element {
+                TreeElementImpl::Node(n) => {
+                    let node = n.borrow();
+                    rows.push(ParentTableRow {
+                        id,
+                        parent_id,
+                        value: Value::Node(node.value.clone()),
+                    });
+                    stack.extend(node.children.iter().rev().map(|c| (c.clone(), Some(id))));
+                }
+                TreeElementImpl::Leaf(l) => rows.push(ParentTableRow {
+                    id,
+                    parent_id,
+                    value: Value::Leaf(l.borrow().value.clone()),
+                }),
+            }
+        }
+        rows
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    // Reconstructs a tree from rows in arbitrary order.
+    // Siblings keep the relative order in which they appear in the rows.
+    pub fn from_parent_table<ID, I>(rows: I) -> Result<Tree<IT, LT>, DLTreeError>
+    where
+        ID: Eq + Hash,
+        I: IntoIterator<Item = ParentTableRow<ID, IT, LT>>,
+    {
+        let rows: Vec<ParentTableRow<ID, IT, LT>> = rows.into_iter().collect();
+        let mut indices = HashMap::with_capacity(rows.len());
+        for (index, row) in rows.iter().enumerate() {
+            if indices.insert(&row.id, index).is_some() {
+                return Err(DLTreeError::DuplicateId);
+            }
+        }
+
+        let mut root = None;
+        let mut children: Vec<Vec<usize>> = (0..rows.len()).map(|_| vec![]).collect();
+        for (index, row) in rows.iter().enumerate() {
+            match &row.parent_id {
+                None => {
+                    if root.replace(index).is_some() {
+                        return Err(DLTreeError::MultipleRoots);
+                    }
+                }
+                Some(parent_id) => {
+                    let parent_index = *indices.get(parent_id).ok_or(DLTreeError::MissingParent)?;
+                    if let Value::Leaf(_) = rows[parent_index].value {
+                        return Err(DLTreeError::LeafWithChildren);
+                    }
+                    children[parent_index].push(index);
+                }
+            }
+        }
+        // All parents exist at this point, so rows without a root can only form cycles
+        let root = match root {
+            Some(root) => root,
+            None if rows.is_empty() => return Err(DLTreeError::MissingRoot),
+            None => return Err(DLTreeError::CycleDetected),
+        };
+
+        let mut values: Vec<Option<Value<IT, LT>>> =
+            rows.into_iter().map(|row| Some(row.value)).collect();
+        let mut take_value = |index: usize| values[index].take().ok_or(DLTreeError::CycleDetected);
+
+        let tree = Tree::new(take_value(root)?);
+        let mut inserted = 1;
+        let mut stack = vec![(tree.root_node(), root)];
+        while let Some((element, index)) = stack.pop() {
+            if let TreeElement::Node(mut node) = element {
+                for child_index in &children[index] {
+                    let child = node.push_back_child(take_value(*child_index)?);
+                    stack.push((child, *child_index));
+                    inserted += 1;
+                }
+            }
+        }
+        // Rows that are not reachable from the root must be part of a cycle
+        if inserted != children.len() {
+            return Err(DLTreeError::CycleDetected);
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encodings::parent_table::ParentTableRow;
+    use crate::tree::{DLTreeError, Tree, Value};
+
+    fn row(
+        id: i64,
+        parent_id: Option<i64>,
+        value: Value<&'static str, u32>,
+    ) -> ParentTableRow<i64, &'static str, u32> {
+        ParentTableRow {
+            id,
+            parent_id,
+            value,
+        }
+    }
+
+    #[test]
+    fn round_trip_test() -> Result<(), DLTreeError> {
+        let tree: Tree<&str, u32> =
+            crate::tree!(node "a" => [leaf 1, node "b" => [leaf 2, node "c"], leaf 3]);
+        let rows = tree.to_parent_table();
+        assert_eq!(
+            rows,
+            vec![
+                ParentTableRow {
+                    id: 0,
+                    parent_id: None,
+                    value: Value::Node("a")
+                },
+                ParentTableRow {
+                    id: 1,
+                    parent_id: Some(0),
+                    value: Value::Leaf(1)
+                },
+                ParentTableRow {
+                    id: 2,
+                    parent_id: Some(0),
+                    value: Value::Node("b")
+                },
+                ParentTableRow {
+                    id: 3,
+                    parent_id: Some(2),
+                    value: Value::Leaf(2)
+                },
+                ParentTableRow {
+                    id: 4,
+                    parent_id: Some(2),
+                    value: Value::Node("c")
+                },
+                ParentTableRow {
+                    id: 5,
+                    parent_id: Some(0),
+                    value: Value::Leaf(3)
+                },
+            ]
+        );
+
+        // Children before parents, siblings keep their relative order
+        let mut shuffled = rows.clone();
+        shuffled.sort_by_key(|r| std::cmp::Reverse(r.parent_id));
+        let rebuilt = Tree::from_parent_table(shuffled)?;
+        assert_eq!(rebuilt.to_parent_table(), rows);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_table_test() {
+        let duplicate = vec![
+            row(1, None, Value::Node("a")),
+            row(1, Some(1), Value::Leaf(2)),
+        ];
+        assert!(matches!(
+            Tree::from_parent_table(duplicate),
+            Err(DLTreeError::DuplicateId)
+        ));
+
+        let missing_parent = vec![
+            row(1, None, Value::Node("a")),
+            row(2, Some(3), Value::Leaf(2)),
+        ];
+        assert!(matches!(
+            Tree::from_parent_table(missing_parent),
+            Err(DLTreeError::MissingParent)
+        ));
+
+        let multiple_roots = vec![row(1, None, Value::Node("a")), row(2, None, Value::Leaf(2))];
+        assert!(matches!(
+            Tree::from_parent_table(multiple_roots),
+            Err(DLTreeError::MultipleRoots)
+        ));
+
+        assert!(matches!(
+            Tree::from_parent_table(Vec::<ParentTableRow<i64, &str, u32>>::new()),
+            Err(DLTreeError::MissingRoot)
+        ));
+
+        let leaf_parent = vec![
+            row(1, None, Value::Leaf(1)),
+            row(2, Some(1), Value::Leaf(2)),
+        ];
+        assert!(matches!(
+            Tree::from_parent_table(leaf_parent),
+            Err(DLTreeError::LeafWithChildren)
+        ));
+
+        let only_cycle = vec![
+            row(1, Some(2), Value::Node("a")),
+            row(2, Some(1), Value::Node("b")),
+        ];
+        assert!(matches!(
+            Tree::from_parent_table(only_cycle),
+            Err(DLTreeError::CycleDetected)
+        ));
+
+        let detached_cycle = vec![
+            row(1, None, Value::Node("root")),
+            row(2, Some(1), Value::Leaf(2)),
+            row(3, Some(4), Value::Node("a")),
+            row(4, Some(3), Value::Node("b")),
+        ];
+        assert!(matches!(
+            Tree::from_parent_table(detached_cycle),
+            Err(DLTreeError::CycleDetected)
+        ));
+    }
+}
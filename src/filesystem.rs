@@ -0,0 +1,411 @@
+use crate::tree::{Tree, Value};
+use crate::tree_elements::tree_element_type::Node;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirEntryInfo {
+    pub path: PathBuf,
+    pub name: OsString,
+    pub is_symlink: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntryInfo {
+    pub path: PathBuf,
+    pub name: OsString,
+    pub len: u64,
+    pub is_symlink: bool,
+}
+
+#[derive(Debug)]
+pub struct DirectoryWalkError {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+pub struct DirectoryTree {
+    pub tree: Tree<DirEntryInfo, FileEntryInfo>,
+    // Errors of single entries that have been skipped during the walk
+    pub errors: Vec<DirectoryWalkError>,
+}
+
+type IgnorePredicate = Box<dyn Fn(&Path) -> bool>;
+
+#[derive(Default)]
+pub struct DirectoryOptions {
+    follow_symlinks: bool,
+    max_depth: Option<usize>,
+    sort_children: bool,
+    ignore: Option<IgnorePredicate>,
+}
+
+impl DirectoryOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Symlinks are reported as files unless they are followed
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    // The root directory has depth 0. Directories at the maximum depth are added without children.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    pub fn sort_children(mut self, sort_children: bool) -> Self {
+        self.sort_children = sort_children;
+        self
+    }
+
+    // Entries for which the predicate returns true are skipped together with their content
+    pub fn ignore<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Path) -> bool + 'static,
+    {
+        self.ignore = Some(Box::new(predicate));
+        self
+    }
+}
+
+// Canonical paths of all directories from the root to the directory that is currently walked.
+// Used to detect symlink loops.
+struct Ancestors {
+    path: PathBuf,
+    parent: Option<Rc<Ancestors>>,
+}
+
+impl Ancestors {
+    fn contains(&self, path: &Path) -> bool {
+        let mut current = Some(self);
+        while let Some(ancestor) = current {
+            if ancestor.path == path {
+                return true;
+            }
+            current = ancestor.parent.as_deref();
+        }
+        false
+    }
+}
+
+struct PendingDirectory {
+    node: Node<DirEntryInfo, FileEntryInfo>,
+    path: PathBuf,
+    depth: usize,
+    ancestors: Option<Rc<Ancestors>>,
+}
+
+enum EntryKind {
+    Directory,
+    File(u64),
+}
+
+fn entry_name(path: &Path) -> OsString {
+    path.file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| path.as_os_str().to_os_string())
+}
+
+fn entry_kind(path: &Path, follow_symlinks: bool) -> io::Result<(EntryKind, bool)> {
+    let link_metadata = fs::symlink_metadata(path)?;
+    let is_symlink = link_metadata.file_type().is_symlink();
+    let metadata = if is_symlink && follow_symlinks {
+        fs::metadata(path)?
+    } else {
+        link_metadata
+    };
+    if metadata.is_dir() {
+        Ok((EntryKind::Directory, is_symlink))
+    } else {
+        Ok((EntryKind::File(metadata.len()), is_symlink))
+    }
+}
+
+// Returns the ancestor chain for a subdirectory if symlinks are followed
+fn descend(parent: &Option<Rc<Ancestors>>, path: &Path) -> io::Result<Option<Rc<Ancestors>>> {
+    match parent {
+        None => Ok(None),
+        Some(parent) => {
+            let canonical = fs::canonicalize(path)?;
+            if parent.contains(&canonical) {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "filesystem loop detected",
+                ));
+            }
+            Ok(Some(Rc::new(Ancestors {
+                path: canonical,
+                parent: Some(parent.clone()),
+            })))
+        }
+    }
+}
+
+impl Tree<DirEntryInfo, FileEntryInfo> {
+    pub fn from_directory<P: AsRef<Path>>(
+        path: P,
+        options: &DirectoryOptions,
+    ) -> io::Result<DirectoryTree> {
+        let root_path = path.as_ref().to_path_buf();
+        let name = entry_name(&root_path);
+        let (kind, is_symlink) = entry_kind(&root_path, true)?;
+        let tree = match kind {
+            EntryKind::File(len) => Tree::new(Value::Leaf(FileEntryInfo {
+                path: root_path.clone(),
+                name,
+                len,
+                is_symlink,
+            })),
+            EntryKind::Directory => Tree::new(Value::Node(DirEntryInfo {
+                path: root_path.clone(),
+                name,
+                is_symlink,
+            })),
+        };
+        let mut errors = vec![];
+        let mut pending = vec![];
+        if let Some(node) = tree.root_node().as_node() {
+            let ancestors = if options.follow_symlinks {
+                Some(Rc::new(Ancestors {
+                    path: fs::canonicalize(&root_path)?,
+                    parent: None,
+                }))
+            } else {
+                None
+            };
+            pending.push(PendingDirectory {
+                node,
+                path: root_path,
+                depth: 0,
+                ancestors,
+            });
+        }
+
+        while let Some(mut directory) = pending.pop() {
+            if options
+                .max_depth
+                .map_or(false, |max| directory.depth >= max)
+            {
+                continue;
+            }
+            let read_dir = match fs::read_dir(&directory.path) {
+                Ok(read_dir) => read_dir,
+                Err(error) => {
+                    errors.push(DirectoryWalkError {
+                        path: directory.path,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            let mut entry_paths = vec![];
+            for entry in read_dir {
+                match entry {
+                    Ok(entry) => entry_paths.push(entry.path()),
+                    Err(error) => errors.push(DirectoryWalkError {
+                        path: directory.path.clone(),
+                        error,
+                    }),
+                }
+            }
+            if options.sort_children {
+                entry_paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+            }
+
+            for entry_path in entry_paths {
+                if options
+                    .ignore
+                    .as_ref()
+                    .map_or(false, |ignore| ignore(&entry_path))
+                {
+                    continue;
+                }
+                let (kind, is_symlink) = match entry_kind(&entry_path, options.follow_symlinks) {
+                    Ok(kind) => kind,
+                    Err(error) => {
+                        errors.push(DirectoryWalkError {
+                            path: entry_path,
+                            error,
+                        });
+                        continue;
+                    }
+                };
+                let name = entry_name(&entry_path);
+                match kind {
+                    EntryKind::File(len) => {
+                        directory.node.push_back_child(Value::Leaf(FileEntryInfo {
+                            path: entry_path,
+                            name,
+                            len,
+                            is_symlink,
+                        }));
+                    }
+                    EntryKind::Directory => {
+                        let ancestors = match descend(&directory.ancestors, &entry_path) {
+                            Ok(ancestors) => Some(ancestors),
+                            Err(error) => {
+                                errors.push(DirectoryWalkError {
+                                    path: entry_path.clone(),
+                                    error,
+                                });
+                                None
+                            }
+                        };
+                        let node = directory
+                            .node
+                            .push_back_child(Value::Node(DirEntryInfo {
+                                path: entry_path.clone(),
+                                name,
+                                is_symlink,
+                            }))
+                            .as_node()
+                            .expect("A child pushed as node value must be a node");
+                        // Directories that would close a loop are added but not walked
+                        if let Some(ancestors) = ancestors {
+                            pending.push(PendingDirectory {
+                                node,
+                                path: entry_path,
+                                depth: directory.depth + 1,
+                                ancestors,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(DirectoryTree { tree, errors })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filesystem::{DirEntryInfo, DirectoryOptions, FileEntryInfo};
+    use crate::test_util::{describe, Describe};
+    use crate::tree::Tree;
+    use crate::tree_elements::tree_element::TreeElement;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> io::Result<Self> {
+            let path = std::env::temp_dir().join(format!("dltree_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(path.join("b/d"))?;
+            fs::create_dir_all(path.join("a"))?;
+            fs::write(path.join("a/file1.txt"), "12345")?;
+            fs::write(path.join("b/file2.txt"), "")?;
+            fs::write(path.join("b/d/file3.txt"), "1")?;
+            fs::write(path.join("c.txt"), "123")?;
+            Ok(TempDir(path))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // Directories are described as name/(children), files as name:len
+    impl Describe for TreeElement<DirEntryInfo, FileEntryInfo> {
+        fn parts(&self) -> (String, Option<Vec<Self>>) {
+            match self {
+                TreeElement::Node(n) => (
+                    format!("{}/", n.value().name.to_string_lossy()),
+                    Some(n.children()),
+                ),
+                TreeElement::Leaf(l) => (
+                    format!("{}:{}", l.value().name.to_string_lossy(), l.value().len),
+                    None,
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn directory_walk_test() -> io::Result<()> {
+        let dir = TempDir::new("walk")?;
+        let options = DirectoryOptions::new().sort_children(true);
+        let walk = Tree::from_directory(&dir.0, &options)?;
+        assert!(walk.errors.is_empty());
+        let name = dir.0.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(
+            describe(&walk.tree.root_node()),
+            format!(
+                "{}/(a/(file1.txt:5),b/(d/(file3.txt:1),file2.txt:0),c.txt:3)",
+                name
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn directory_options_test() -> io::Result<()> {
+        let dir = TempDir::new("options")?;
+        let options = DirectoryOptions::new()
+            .sort_children(true)
+            .max_depth(1)
+            .ignore(|path| path.extension().map_or(false, |e| e == "txt"));
+        let walk = Tree::from_directory(&dir.0, &options)?;
+        let name = dir.0.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(
+            describe(&walk.tree.root_node()),
+            format!("{}/(a/(),b/())", name)
+        );
+
+        let file = Tree::from_directory(dir.0.join("c.txt"), &options)?;
+        assert_eq!(describe(&file.tree.root_node()), "c.txt:3");
+        assert!(Tree::from_directory(dir.0.join("missing"), &options).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_test() -> io::Result<()> {
+        let dir = TempDir::new("symlink")?;
+        std::os::unix::fs::symlink(&dir.0, dir.0.join("b/loop"))?;
+        std::os::unix::fs::symlink(dir.0.join("missing"), dir.0.join("dangling"))?;
+
+        let not_followed = Tree::from_directory(&dir.0, &DirectoryOptions::new())?;
+        assert!(not_followed.errors.is_empty());
+        let links = symlink_names(&not_followed.tree.root_node());
+        assert_eq!(links, vec!["dangling".to_string(), "loop".to_string()]);
+
+        let followed = Tree::from_directory(
+            &dir.0,
+            &DirectoryOptions::new()
+                .follow_symlinks(true)
+                .sort_children(true),
+        )?;
+        let mut error_names = followed
+            .errors
+            .iter()
+            .map(|e| e.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+        error_names.sort();
+        assert_eq!(error_names, vec!["dangling", "loop"]);
+        Ok(())
+    }
+
+    fn symlink_names(element: &TreeElement<DirEntryInfo, FileEntryInfo>) -> Vec<String> {
+        let mut names = match element {
+            TreeElement::Node(n) => n.children().iter().flat_map(symlink_names).collect(),
+            TreeElement::Leaf(l) if l.value().is_symlink => {
+                vec![l.value().name.to_string_lossy().to_string()]
+            }
+            TreeElement::Leaf(_) => vec![],
+        };
+        names.sort();
+        names
+    }
+}
@@ -0,0 +1,182 @@
+use crate::internal::node_impl::NodeImpl;
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{DLTreeError, Tree, Value};
+use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::tree_element_type::Node;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+impl<IT, LT> Node<IT, LT> {
+    // Removes all children for which predicate returns false. The predicate is called for the
+    // children from first to last, removed children are detached like by remove_all_children.
+    pub fn retain_children<F>(&mut self, mut predicate: F) -> Result<(), DLTreeError>
+    where
+        F: FnMut(&TreeElement<IT, LT>) -> bool,
+    {
+        let children = self.children();
+        let removed: Vec<usize> = children
+            .iter()
+            .enumerate()
+            .filter(|(_, child)| !predicate(child))
+            .map(|(index, _)| index)
+            .collect();
+        // Removing from the back keeps the indexes of the remaining removals valid
+        for index in removed.into_iter().rev() {
+            self.remove_child_at(index, &children[index].to_impl())?;
+        }
+        Ok(())
+    }
+}
+
+struct FilterFrame<IT, LT> {
+    node: Rc<RefCell<NodeImpl<IT, LT>>>,
+    next_child: usize,
+    kept: Vec<TreeElementImpl<IT, LT>>,
+}
+
+impl<IT: Clone, LT: Clone> Tree<IT, LT> {
+    // Returns a copy of this tree that only contains the leaves for which keep_leaf returns true
+    // and their ancestors. Nodes without kept leaves are dropped, so None is returned if no leaf
+    // is kept at all.
+    pub fn filter<F>(&self, mut keep_leaf: F) -> Option<Tree<IT, LT>>
+    where
+        F: FnMut(&LT) -> bool,
+    {
+        let root = match &self.tree {
+            TreeElementImpl::Leaf(l) => {
+                let value = l.borrow().value.clone();
+                return keep_leaf(&value).then(|| Tree::new(Value::Leaf(value)));
+            }
+            TreeElementImpl::Node(n) => n.clone(),
+        };
+        let mut stack = vec![FilterFrame {
+            node: root,
+            next_child: 0,
+            kept: vec![],
+        }];
+        loop {
+            let frame = stack.last_mut()?;
+            let child = frame.node.borrow().children.get(frame.next_child).cloned();
+            frame.next_child += 1;
+            match child {
+                Some(TreeElementImpl::Leaf(l)) => {
+                    let value = l.borrow().value.clone();
+                    if keep_leaf(&value) {
+                        frame
+                            .kept
+                            .push(TreeElementImpl::new(Value::Leaf(value), None));
+                    }
+                }
+                Some(TreeElementImpl::Node(n)) => stack.push(FilterFrame {
+                    node: n,
+                    next_child: 0,
+                    kept: vec![],
+                }),
+                None => {
+                    let frame = stack.pop()?;
+                    if frame.kept.is_empty() {
+                        continue;
+                    }
+                    let value = frame.node.borrow().value.clone();
+                    let copy = TreeElementImpl::new(Value::Node(value), None);
+                    if let TreeElementImpl::Node(n) = &copy {
+                        for mut child in frame.kept {
+                            child.update_parent(Some(Rc::downgrade(n)));
+                            n.borrow_mut().children.push_back(child);
+                        }
+                    }
+                    match stack.last_mut() {
+                        Some(parent) => parent.kept.push(copy),
+                        None => return Some(Tree { tree: copy }),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    // Removes every subtree whose topmost element matches predicate. The root is never removed
+    // and the predicate is not called for elements below a removed element.
+    pub fn prune<F>(&self, mut predicate: F) -> Result<(), DLTreeError>
+    where
+        F: FnMut(&TreeElement<IT, LT>) -> bool,
+    {
+        let mut stack: Vec<Node<IT, LT>> = self.root_node().as_node().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            node.retain_children(|child| !predicate(child))?;
+            let children = node.children();
+            stack.extend(children.into_iter().rev().filter_map(|c| c.as_node()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{build_tree, describe};
+    use crate::tree::{DLTreeError, Tree, Value};
+    use crate::tree_elements::tree_element::TreeElement;
+
+    #[test]
+    fn retain_children_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        let mut root = tree.root_node().as_node().unwrap();
+        let children = root.children();
+        let mut visited = vec![];
+        root.retain_children(|child| {
+            visited.push(child.as_leaf().map(|l| *l.value()));
+            child.as_node().is_some()
+        })?;
+        assert_eq!(visited, vec![Some(1), None, None, Some(5)]);
+        assert_eq!(describe(&tree.root_node()), "a(b(2,c(3)),d(4,e()))");
+        assert!(children[0].parent()?.is_none());
+        assert!(children[3].parent()?.is_none());
+        assert!(children[1].parent()?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn filter_test() {
+        let tree = build_tree();
+        let even = tree.filter(|v| v % 2 == 0).unwrap();
+        assert_eq!(describe(&even.root_node()), "a(b(2),d(4))");
+        let three = tree.filter(|v| *v == 3).unwrap();
+        assert_eq!(describe(&three.root_node()), "a(b(c(3)))");
+        let c = three.root_node().as_node().unwrap().children()[0]
+            .as_node()
+            .unwrap()
+            .children()[0]
+            .clone();
+        assert!(c.parent().unwrap().is_some());
+        assert!(tree.filter(|v| *v > 5).is_none());
+        // The original tree is not changed
+        assert_eq!(describe(&tree.root_node()), "a(1,b(2,c(3)),d(4,e()),5)");
+
+        let leaf = Tree::<String, u32>::new(Value::Leaf(1));
+        assert!(leaf.filter(|v| *v == 1).is_some());
+        assert!(leaf.filter(|v| *v == 2).is_none());
+    }
+
+    #[test]
+    fn prune_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        tree.enable_journal(10);
+        let b = tree.root_node().as_node().unwrap().children()[1].clone();
+        let mut visited = vec![];
+        tree.prune(|element| match element {
+            TreeElement::Node(n) => {
+                visited.push(n.value().clone());
+                *n.value() == "b" || *n.value() == "e"
+            }
+            TreeElement::Leaf(l) => *l.value() == 4,
+        })?;
+        assert_eq!(visited, vec!["b", "d", "e"]);
+        assert_eq!(describe(&tree.root_node()), "a(1,d(),5)");
+        assert!(b.parent()?.is_none());
+
+        while tree.undo()? {}
+        assert_eq!(describe(&tree.root_node()), "a(1,b(2,c(3)),d(4,e()),5)");
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{Tree, Value};
+use crate::tree_elements::tree_element_type::Node;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+// The entries are stored in breadth-first order, so the children of every node are contiguous
+#[derive(Debug, Clone)]
+struct FrozenEntry<IT, LT> {
+    value: Value<IT, LT>,
+    parent: Option<usize>,
+    depth: usize,
+    children: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FrozenTree<IT, LT> {
+    entries: Vec<FrozenEntry<IT, LT>>,
+}
+
+#[derive(Debug)]
+pub struct FrozenElement<'a, IT, LT> {
+    tree: &'a FrozenTree<IT, LT>,
+    index: usize,
+}
+
+impl<IT: Clone, LT: Clone> Tree<IT, LT> {
+    pub fn freeze(&self) -> FrozenTree<IT, LT> {
+        let mut entries = vec![];
+        let mut queue = VecDeque::new();
+        queue.push_back((self.tree.clone(), None, 0));
+        while let Some((element, parent, depth)) = queue.pop_front() {
+            let (value, children) = match element {
+                TreeElementImpl::Node(n) => {
+                    let node = n.borrow();
+                    let first_child = entries.len() + 1 + queue.len();
+                    let index = entries.len();
+                    queue.extend(
+                        node.children
+                            .iter()
+                            .map(|c| (c.clone(), Some(index), depth + 1)),
+                    );
+                    (
+                        Value::Node(node.value.clone()),
+                        first_child..first_child + node.children.len(),
+                    )
+                }
+                TreeElementImpl::Leaf(l) => (Value::Leaf(l.borrow().value.clone()), 0..0),
+            };
+            entries.push(FrozenEntry {
+                value,
+                parent,
+                depth,
+                children,
+            });
+        }
+        FrozenTree { entries }
+    }
+}
+
+impl<IT, LT> FrozenTree<IT, LT> {
+    pub fn root(&self) -> FrozenElement<'_, IT, LT> {
+        FrozenElement {
+            tree: self,
+            index: 0,
+        }
+    }
+
+    // Elements are indexed in breadth-first order starting with 0 at the root
+    pub fn get(&self, index: usize) -> Option<FrozenElement<'_, IT, LT>> {
+        if index < self.entries.len() {
+            Some(FrozenElement { tree: self, index })
+        } else {
+            None
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // A frozen tree always contains at least its root
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = FrozenElement<'_, IT, LT>> {
+        (0..self.entries.len()).map(move |index| FrozenElement { tree: self, index })
+    }
+}
+
+impl<IT: Clone, LT: Clone> FrozenTree<IT, LT> {
+    pub fn thaw(&self) -> Tree<IT, LT> {
+        let tree = Tree::new(self.entries[0].value.clone());
+        let mut nodes: Vec<Option<Node<IT, LT>>> = Vec::with_capacity(self.entries.len());
+        nodes.push(tree.root_node().as_node());
+        // Breadth-first order guarantees that parents are created before their children
+        // and that siblings are visited in their original order
+        for entry in &self.entries[1..] {
+            let mut parent = entry
+                .parent
+                .and_then(|parent| nodes[parent].clone())
+                .expect("The parent of a frozen element must be a node");
+            nodes.push(parent.push_back_child(entry.value.clone()).as_node());
+        }
+        tree
+    }
+}
+
+impl<'a, IT, LT> FrozenElement<'a, IT, LT> {
+    fn entry(&self) -> &'a FrozenEntry<IT, LT> {
+        &self.tree.entries[self.index]
+    }
+
+    fn at(&self, index: usize) -> FrozenElement<'a, IT, LT> {
+        FrozenElement {
+            tree: self.tree,
+            index,
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn value(&self) -> &'a Value<IT, LT> {
+        &self.entry().value
+    }
+
+    pub fn node_value(&self) -> Option<&'a IT> {
+        match self.value() {
+            Value::Node(n) => Some(n),
+            Value::Leaf(_) => None,
+        }
+    }
+
+    pub fn leaf_value(&self) -> Option<&'a LT> {
+        match self.value() {
+            Value::Node(_) => None,
+            Value::Leaf(l) => Some(l),
+        }
+    }
+
+    pub fn is_node(&self) -> bool {
+        self.node_value().is_some()
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.leaf_value().is_some()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.entry().depth
+    }
+
+    pub fn parent(&self) -> Option<FrozenElement<'a, IT, LT>> {
+        self.entry().parent.map(|parent| self.at(parent))
+    }
+
+    pub fn children(&self) -> impl ExactSizeIterator<Item = FrozenElement<'a, IT, LT>> {
+        let tree = self.tree;
+        self.entry()
+            .children
+            .clone()
+            .map(move |index| FrozenElement { tree, index })
+    }
+
+    pub fn child(&self, index: usize) -> Option<FrozenElement<'a, IT, LT>> {
+        let children = &self.entry().children;
+        if index < children.len() {
+            Some(self.at(children.start + index))
+        } else {
+            None
+        }
+    }
+
+    pub fn child_count(&self) -> usize {
+        self.entry().children.len()
+    }
+
+    // Position among the children of the parent. The root has position 0.
+    pub fn sibling_index(&self) -> usize {
+        match self.parent() {
+            None => 0,
+            Some(parent) => self.index - parent.entry().children.start,
+        }
+    }
+
+    pub fn next_sibling(&self) -> Option<FrozenElement<'a, IT, LT>> {
+        let siblings = &self.parent()?.entry().children;
+        if self.index + 1 < siblings.end {
+            Some(self.at(self.index + 1))
+        } else {
+            None
+        }
+    }
+
+    pub fn prev_sibling(&self) -> Option<FrozenElement<'a, IT, LT>> {
+        let siblings = &self.parent()?.entry().children;
+        if self.index > siblings.start {
+            Some(self.at(self.index - 1))
+        } else {
+            None
+        }
+    }
+}
+
+impl<IT, LT> Clone for FrozenElement<'_, IT, LT> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<IT, LT> Copy for FrozenElement<'_, IT, LT> {}
+
+impl<IT, LT> PartialEq for FrozenElement<'_, IT, LT> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.tree, other.tree) && self.index == other.index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frozen::FrozenTree;
+    use crate::test_util::{build_tree, describe};
+    use crate::tree::{Tree, Value};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn navigation_test() {
+        let frozen = build_tree().freeze();
+        assert_eq!(frozen.len(), 10);
+        let root = frozen.root();
+        assert!(root.parent().is_none());
+        assert_eq!(root.child_count(), 4);
+        assert_eq!(describe(&root), "a(1,b(2,c(3)),d(4,e()),5)");
+
+        let b = root.child(1).unwrap();
+        assert_eq!(b.node_value().unwrap(), "b");
+        assert_eq!(b.depth(), 1);
+        assert_eq!(b.sibling_index(), 1);
+        assert_eq!(b.parent().unwrap(), root);
+        assert_eq!(*b.prev_sibling().unwrap().leaf_value().unwrap(), 1);
+        assert_eq!(b.next_sibling().unwrap().node_value().unwrap(), "d");
+        assert!(root.child(0).unwrap().prev_sibling().is_none());
+        assert!(root.child(3).unwrap().next_sibling().is_none());
+        assert!(root.child(4).is_none());
+
+        let c = b.child(1).unwrap();
+        let leaf3 = c.child(0).unwrap();
+        assert!(leaf3.is_leaf());
+        assert_eq!(leaf3.depth(), 3);
+        assert!(leaf3.next_sibling().is_none());
+        assert_eq!(leaf3.parent().unwrap().parent().unwrap(), b);
+        let e = root.child(2).unwrap().child(1).unwrap();
+        assert!(e.is_node());
+        assert_eq!(e.child_count(), 0);
+        assert_eq!(frozen.get(leaf3.index()).unwrap(), leaf3);
+        assert!(frozen.get(10).is_none());
+    }
+
+    #[test]
+    fn thaw_test() {
+        let tree = build_tree();
+        let frozen = tree.freeze();
+        *tree.root_node().as_node().unwrap().value_mut() = "changed".to_string();
+        let thawed = frozen.thaw();
+        assert_eq!(
+            describe(&thawed.freeze().root()),
+            "a(1,b(2,c(3)),d(4,e()),5)"
+        );
+        thawed
+            .root_node()
+            .as_node()
+            .unwrap()
+            .push_back_child(Value::Leaf(5));
+        assert_eq!(describe(&frozen.root()), "a(1,b(2,c(3)),d(4,e()),5)");
+
+        let leaf = Tree::<String, u32>::new(Value::Leaf(9)).freeze();
+        assert_eq!(*leaf.thaw().root_node().as_leaf().unwrap().value(), 9);
+    }
+
+    #[test]
+    fn send_sync_test() {
+        let frozen: Arc<FrozenTree<String, u32>> = Arc::new(build_tree().freeze());
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let frozen = frozen.clone();
+                thread::spawn(move || describe(&frozen.root()))
+            })
+            .collect();
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), "a(1,b(2,c(3)),d(4,e()),5)");
+        }
+    }
+}
@@ -1,17 +1,24 @@
 use crate::internal::node_impl::NodeImpl;
+use crate::internal::observers::Observers;
+use crate::internal::tree_element_impl::TreeElementImpl;
 use crate::internal::TreeElementTrait;
 use std::cell::RefCell;
-use std::rc::Weak;
+use std::rc::{Rc, Weak};
 
 #[derive(Debug)]
 pub struct LeafImpl<IT, LT> {
     pub parent: Option<Weak<RefCell<NodeImpl<IT, LT>>>>,
     pub value: LT,
+    pub observers: Observers<IT, LT>,
 }
 
 impl<IT, LT> LeafImpl<IT, LT> {
     pub fn new(value: LT, parent: Option<Weak<RefCell<NodeImpl<IT, LT>>>>) -> Self {
-        LeafImpl { parent, value }
+        LeafImpl {
+            parent,
+            value,
+            observers: Observers::default(),
+        }
     }
 }
 
@@ -19,6 +26,9 @@ impl<IT, LT> TreeElementTrait<IT, LT> for LeafImpl<IT, LT> {
     fn parent(&mut self) -> &mut Option<Weak<RefCell<NodeImpl<IT, LT>>>> {
         &mut self.parent
     }
+    fn to_impl(element: &Rc<RefCell<Self>>) -> TreeElementImpl<IT, LT> {
+        TreeElementImpl::Leaf(element.clone())
+    }
 }
 
 impl<IT: Clone, LT: Clone> Clone for LeafImpl<IT, LT> {
@@ -26,6 +36,7 @@ impl<IT: Clone, LT: Clone> Clone for LeafImpl<IT, LT> {
         LeafImpl {
             parent: None,
             value: self.value.clone(),
+            observers: Observers::default(),
         }
     }
 }
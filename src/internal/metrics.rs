@@ -0,0 +1,208 @@
+use crate::internal::node_impl::NodeImpl;
+use crate::internal::tree_element_impl::TreeElementImpl;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+// Sizes of the subtree below a node, the node itself included.
+// If a node caches its metrics, all nodes below it cache their metrics as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubtreeMetrics {
+    pub len: usize,
+    pub leaves: usize,
+    pub height: usize,
+}
+
+const LEAF: SubtreeMetrics = SubtreeMetrics {
+    len: 1,
+    leaves: 1,
+    height: 0,
+};
+
+// Walks the subtree without using or updating any cached metrics
+pub fn measure<IT, LT>(element: &TreeElementImpl<IT, LT>) -> SubtreeMetrics {
+    let mut metrics = SubtreeMetrics {
+        len: 0,
+        leaves: 0,
+        height: 0,
+    };
+    let mut stack = vec![(element.clone(), 0)];
+    while let Some((element, depth)) = stack.pop() {
+        metrics.len += 1;
+        metrics.height = metrics.height.max(depth);
+        match element {
+            TreeElementImpl::Leaf(_) => metrics.leaves += 1,
+            TreeElementImpl::Node(n) => {
+                stack.extend(n.borrow().children.iter().map(|c| (c.clone(), depth + 1)))
+            }
+        }
+    }
+    metrics
+}
+
+// Calculates and caches the metrics of all nodes below node that have no cached metrics yet
+pub fn compute<IT, LT>(node: &Rc<RefCell<NodeImpl<IT, LT>>>) -> SubtreeMetrics {
+    // The metrics of a node are calculated after the metrics of all of its children
+    let mut stack = vec![(node.clone(), false)];
+    while let Some((current, children_done)) = stack.pop() {
+        if children_done {
+            let mut metrics = SubtreeMetrics {
+                len: 1,
+                leaves: 0,
+                height: 0,
+            };
+            for child in current.borrow().children.iter() {
+                let child_metrics = metrics_of(child);
+                metrics.len += child_metrics.len;
+                metrics.leaves += child_metrics.leaves;
+                metrics.height = metrics.height.max(child_metrics.height + 1);
+            }
+            current.borrow().metrics.set(Some(metrics));
+        } else if current.borrow().metrics.get().is_none() {
+            stack.push((current.clone(), true));
+            for child in current.borrow().children.iter() {
+                if let TreeElementImpl::Node(n) = child {
+                    stack.push((n.clone(), false));
+                }
+            }
+        }
+    }
+    metrics_of(&TreeElementImpl::Node(node.clone()))
+}
+
+fn metrics_of<IT, LT>(element: &TreeElementImpl<IT, LT>) -> SubtreeMetrics {
+    match element {
+        TreeElementImpl::Leaf(_) => LEAF,
+        TreeElementImpl::Node(n) => {
+            let cached = n.borrow().metrics.get();
+            cached.unwrap_or_else(|| compute(n))
+        }
+    }
+}
+
+// The cached metrics of element, None if element is a node whose value is mutably borrowed
+fn cached<IT, LT>(element: &TreeElementImpl<IT, LT>) -> Option<SubtreeMetrics> {
+    match element {
+        TreeElementImpl::Leaf(_) => Some(LEAF),
+        TreeElementImpl::Node(n) => n.try_borrow().ok()?.metrics.get(),
+    }
+}
+
+// Calculates the metrics of node from the cached metrics of its children
+fn recalculate<IT, LT>(node: &NodeImpl<IT, LT>) -> Option<SubtreeMetrics> {
+    let mut metrics = SubtreeMetrics {
+        len: 1,
+        leaves: 0,
+        height: 0,
+    };
+    for child in node.children.iter() {
+        let child_metrics = cached(child)?;
+        metrics.len += child_metrics.len;
+        metrics.leaves += child_metrics.leaves;
+        metrics.height = metrics.height.max(child_metrics.height + 1);
+    }
+    Some(metrics)
+}
+
+// Updates the cached metrics of parent and its ancestors after removed has been removed from
+// the children of parent and inserted has been inserted. Does nothing if parent caches no metrics.
+// Must be called without any borrow on the changed elements. The update stops at nodes whose
+// value or whose children are mutably borrowed, their guards refresh them when they are dropped.
+pub fn update<IT, LT>(
+    parent: &Rc<RefCell<NodeImpl<IT, LT>>>,
+    removed: Option<&TreeElementImpl<IT, LT>>,
+    inserted: Option<&TreeElementImpl<IT, LT>>,
+) {
+    if parent.borrow().metrics.get().is_none() {
+        return;
+    }
+    let mut removed = removed.map(metrics_of);
+    let mut inserted = inserted.map(metrics_of);
+    let mut current = parent.clone();
+    loop {
+        let (before, after, next) = {
+            let node = match current.try_borrow() {
+                Ok(node) => node,
+                Err(_) => return,
+            };
+            let before = match node.metrics.get() {
+                Some(metrics) => metrics,
+                None => return,
+            };
+            let mut after = before;
+            if let Some(removed) = removed {
+                after.len -= removed.len;
+                after.leaves -= removed.leaves;
+            }
+            if let Some(inserted) = inserted {
+                after.len += inserted.len;
+                after.leaves += inserted.leaves;
+                after.height = after.height.max(inserted.height + 1);
+            }
+            // The height can only shrink if the removed child was one of the highest children
+            let highest_removed = removed.map_or(false, |r| r.height + 1 == before.height)
+                && inserted.map_or(true, |i| i.height + 1 < before.height);
+            if highest_removed {
+                after.height = match recalculate(&node) {
+                    Some(metrics) => metrics.height,
+                    None => return,
+                };
+            }
+            node.metrics.set(Some(after));
+            (before, after, node.parent.as_ref().and_then(Weak::upgrade))
+        };
+        if before == after {
+            return;
+        }
+        removed = Some(before);
+        inserted = Some(after);
+        match next {
+            Some(next) => current = next,
+            None => return,
+        }
+    }
+}
+
+// Recalculates the cached metrics of node after its value has been mutably borrowed, and those
+// of its ancestors as long as they change. The parent is always recalculated, as update may have
+// stopped there because node was borrowed.
+pub fn refresh<IT, LT>(borrowed: &Rc<RefCell<NodeImpl<IT, LT>>>) {
+    let mut current = borrowed.clone();
+    loop {
+        let next = {
+            let node = match current.try_borrow() {
+                Ok(node) => node,
+                Err(_) => return,
+            };
+            let before = match node.metrics.get() {
+                Some(metrics) => metrics,
+                None => return,
+            };
+            let after = match recalculate(&node) {
+                Some(metrics) => metrics,
+                None => return,
+            };
+            node.metrics.set(Some(after));
+            if before == after && !Rc::ptr_eq(&current, borrowed) {
+                return;
+            }
+            node.parent.as_ref().and_then(Weak::upgrade)
+        };
+        match next {
+            Some(next) => current = next,
+            None => return,
+        }
+    }
+}
+
+pub fn clear<IT, LT>(node: &Rc<RefCell<NodeImpl<IT, LT>>>) {
+    let mut stack = vec![node.clone()];
+    while let Some(current) = stack.pop() {
+        let current = current.borrow();
+        current.metrics.set(None);
+        for child in current.children.iter() {
+            if let TreeElementImpl::Node(n) = child {
+                stack.push(n.clone());
+            }
+        }
+    }
+}
@@ -1,11 +1,17 @@
 use crate::internal::node_impl::NodeImpl;
+use crate::internal::tree_element_impl::TreeElementImpl;
 use std::cell::RefCell;
-use std::rc::Weak;
+use std::rc::{Rc, Weak};
 
 pub mod leaf_impl;
+pub mod metrics;
 pub mod node_impl;
+pub mod observers;
 pub mod tree_element_impl;
 
 pub trait TreeElementTrait<IT, LT> {
     fn parent(&mut self) -> &mut Option<Weak<RefCell<NodeImpl<IT, LT>>>>;
+    fn to_impl(element: &Rc<RefCell<Self>>) -> TreeElementImpl<IT, LT>
+    where
+        Self: Sized;
 }
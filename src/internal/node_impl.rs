@@ -1,14 +1,23 @@
 use super::tree_element_impl::TreeElementImpl;
+use crate::internal::metrics::SubtreeMetrics;
+use crate::internal::observers::Observers;
 use crate::internal::TreeElementTrait;
-use std::cell::RefCell;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::collections::VecDeque;
-use std::rc::Weak;
+use std::rc::{Rc, Weak};
 
 #[derive(Debug)]
 pub struct NodeImpl<IT, LT> {
     pub parent: Option<Weak<RefCell<NodeImpl<IT, LT>>>>,
     pub value: IT,
     pub children: VecDeque<TreeElementImpl<IT, LT>>,
+    pub observers: Observers<IT, LT>,
+    // Cached metrics of the subtree, they can be updated while the node is borrowed
+    pub metrics: Cell<Option<SubtreeMetrics>>,
+    // The value of the aggregate that is enabled on the tree. It can be updated while the node
+    // is borrowed by other handles.
+    pub aggregate: RefCell<Option<Box<dyn Any>>>,
 }
 
 impl<IT, LT> NodeImpl<IT, LT> {
@@ -17,6 +26,9 @@ impl<IT, LT> NodeImpl<IT, LT> {
             parent,
             value,
             children: VecDeque::new(),
+            observers: Observers::default(),
+            metrics: Cell::new(None),
+            aggregate: RefCell::new(None),
         }
     }
 }
@@ -25,6 +37,9 @@ impl<IT, LT> TreeElementTrait<IT, LT> for NodeImpl<IT, LT> {
     fn parent(&mut self) -> &mut Option<Weak<RefCell<NodeImpl<IT, LT>>>> {
         &mut self.parent
     }
+    fn to_impl(element: &Rc<RefCell<Self>>) -> TreeElementImpl<IT, LT> {
+        TreeElementImpl::Node(element.clone())
+    }
 }
 
 impl<IT: Clone, LT: Clone> crate::DeepClone for NodeImpl<IT, LT> {
@@ -33,6 +48,9 @@ impl<IT: Clone, LT: Clone> crate::DeepClone for NodeImpl<IT, LT> {
             parent: None,
             value: self.value.clone(),
             children: self.children.iter().map(|c| c.deep_clone()).collect(),
+            observers: Observers::default(),
+            metrics: Cell::new(None),
+            aggregate: RefCell::new(None),
         }
     }
 }
@@ -0,0 +1,328 @@
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::journal::{Edit, EditJournal};
+use crate::observer::{ObserverId, TreeEvent, TreeObserver};
+use crate::tree::{DLTreeError, Value};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+thread_local! {
+    static NEXT_OBSERVER_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+// An observer together with the events that have occurred while it was handling an event
+pub struct ObserverCell<IT, LT, O: ?Sized> {
+    pending: RefCell<VecDeque<TreeEvent<IT, LT>>>,
+    observer: RefCell<O>,
+}
+
+pub type SharedObserver<IT, LT> = Rc<ObserverCell<IT, LT, dyn TreeObserver<IT, LT>>>;
+pub type SharedJournal<IT, LT> = Rc<RefCell<EditJournal<IT, LT>>>;
+pub type WeakJournal<IT, LT> = Weak<RefCell<EditJournal<IT, LT>>>;
+// The value an element had before it was borrowed, as copied by one of the journals
+pub type ValueSnapshot<IT, LT> = (SharedJournal<IT, LT>, Value<IT, LT>);
+
+impl<IT, LT, O> ObserverCell<IT, LT, O> {
+    pub fn new(observer: O) -> Self {
+        ObserverCell {
+            pending: RefCell::new(VecDeque::new()),
+            observer: RefCell::new(observer),
+        }
+    }
+}
+
+impl<IT, LT, O: TreeObserver<IT, LT> + ?Sized> ObserverCell<IT, LT, O> {
+    // An observer that changes the tree while it handles an event receives the events of
+    // these changes after it has returned
+    fn deliver(&self, event: &TreeEvent<IT, LT>) {
+        let mut observer = match self.observer.try_borrow_mut() {
+            Ok(observer) => observer,
+            Err(_) => {
+                self.pending.borrow_mut().push_back(event.clone());
+                return;
+            }
+        };
+        observer.on_event(event);
+        loop {
+            let next = self.pending.borrow_mut().pop_front();
+            match next {
+                Some(event) => observer.on_event(&event),
+                None => return,
+            }
+        }
+    }
+}
+
+type Registration<IT, LT> = (ObserverId, SharedObserver<IT, LT>);
+
+// Observers registered on a tree element
+pub struct Observers<IT, LT> {
+    registrations: Vec<Registration<IT, LT>>,
+    journal: Option<SharedJournal<IT, LT>>,
+    // The observer that maintains the aggregates of the nodes
+    pub aggregate: Option<ObserverId>,
+    // The journal that has removed this element from its tree. It keeps recording the edits
+    // of this element as long as the element is not inserted into a tree again.
+    pub detached_journal: Option<WeakJournal<IT, LT>>,
+    // The recipients of the parent while its value is mutably borrowed
+    borrowed_parent: RefCell<Weak<BorrowedParent<IT, LT>>>,
+}
+
+impl<IT, LT> Observers<IT, LT> {
+    pub fn add(&mut self, observer: SharedObserver<IT, LT>) -> ObserverId {
+        let id = NEXT_OBSERVER_ID.with(|next| {
+            let id = next.get();
+            next.set(id + 1);
+            ObserverId(id)
+        });
+        self.registrations.push((id, observer));
+        id
+    }
+
+    pub fn remove(&mut self, id: ObserverId) -> bool {
+        let len = self.registrations.len();
+        self.registrations
+            .retain(|(registered_id, _)| *registered_id != id);
+        len != self.registrations.len()
+    }
+
+    pub fn journal(&self) -> Option<&SharedJournal<IT, LT>> {
+        self.journal.as_ref()
+    }
+
+    pub fn set_journal(&mut self, journal: Option<SharedJournal<IT, LT>>) {
+        self.journal = journal;
+    }
+
+    fn borrowed_parent(&self) -> Weak<BorrowedParent<IT, LT>> {
+        self.borrowed_parent.borrow().clone()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SharedObserver<IT, LT>> {
+        self.registrations.iter().map(|(_, observer)| observer)
+    }
+}
+
+impl<IT, LT> Default for Observers<IT, LT> {
+    fn default() -> Self {
+        Observers {
+            registrations: Vec::new(),
+            journal: None,
+            aggregate: None,
+            detached_journal: None,
+            borrowed_parent: RefCell::new(Weak::new()),
+        }
+    }
+}
+
+impl<IT, LT> fmt::Debug for Observers<IT, LT> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.iter().count())
+    }
+}
+
+// The observers and journals of an element and of all of its ancestors
+pub struct Recipients<IT, LT> {
+    observers: Vec<SharedObserver<IT, LT>>,
+    journals: Vec<SharedJournal<IT, LT>>,
+    // The mutably borrowed ancestor whose guard delivers the events when it is dropped
+    deferred_to: Option<Rc<BorrowedParent<IT, LT>>>,
+}
+
+impl<IT, LT> Recipients<IT, LT> {
+    // Has to be called before the element or its children are changed. Fails only if the element
+    // itself is mutably borrowed. The recipients above a mutably borrowed ancestor are taken from
+    // the guard of its value, if that guard has lent them to the children of the ancestor.
+    pub fn collect(start: &TreeElementImpl<IT, LT>) -> Result<Self, DLTreeError> {
+        let mut recipients = Recipients::default();
+        let (mut parent, mut detached_journal, mut borrowed_parent) = match start {
+            TreeElementImpl::Node(n) => {
+                let node = n.try_borrow().map_err(|_| DLTreeError::ValueBorrowed)?;
+                recipients.add(&node.observers);
+                (
+                    node.parent.clone(),
+                    node.observers.detached_journal.clone(),
+                    node.observers.borrowed_parent(),
+                )
+            }
+            TreeElementImpl::Leaf(l) => {
+                let leaf = l.try_borrow().map_err(|_| DLTreeError::ValueBorrowed)?;
+                recipients.add(&leaf.observers);
+                (
+                    leaf.parent.clone(),
+                    leaf.observers.detached_journal.clone(),
+                    leaf.observers.borrowed_parent(),
+                )
+            }
+        };
+        while let Some(node) = parent.and_then(|p| p.upgrade()) {
+            let node = match node.try_borrow() {
+                Ok(node) => node,
+                Err(_) => {
+                    // Nothing above the ancestor is observed if its guard has lent nothing
+                    if let Some(borrowed_parent) = borrowed_parent.upgrade() {
+                        recipients
+                            .observers
+                            .extend(borrowed_parent.recipients.observers.iter().cloned());
+                        recipients
+                            .journals
+                            .extend(borrowed_parent.recipients.journals.iter().cloned());
+                        recipients.deferred_to = Some(borrowed_parent);
+                    }
+                    return Ok(recipients);
+                }
+            };
+            recipients.add(&node.observers);
+            parent = node.parent.clone();
+            detached_journal = node.observers.detached_journal.clone();
+            borrowed_parent = node.observers.borrowed_parent();
+        }
+        // Only the journal of the topmost element is relevant, the other elements are attached
+        if let Some(journal) = detached_journal.and_then(|j| j.upgrade()) {
+            recipients.journals.push(journal);
+        }
+        Ok(recipients)
+    }
+
+    fn add(&mut self, observers: &Observers<IT, LT>) {
+        self.observers.extend(observers.iter().cloned());
+        self.journals.extend(observers.journal.iter().cloned());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty() && self.journals.is_empty()
+    }
+
+    // Copies the current value of the element for all journals, before it is borrowed
+    pub fn snapshot_value(&self, element: &TreeElementImpl<IT, LT>) -> Vec<ValueSnapshot<IT, LT>> {
+        self.journals
+            .iter()
+            .filter_map(|journal| {
+                let value = journal.try_borrow().ok()?.snapshot(element);
+                Some((journal.clone(), value))
+            })
+            .collect()
+    }
+
+    // Makes these recipients of element available to its children while the value of element is
+    // mutably borrowed. Nothing is lent if there are no recipients, so changes below the element
+    // are then not reported above it.
+    pub fn lend(&self, element: &TreeElementImpl<IT, LT>) -> Option<Rc<BorrowedParent<IT, LT>>> {
+        let node = match element {
+            TreeElementImpl::Node(n) if !self.is_empty() => n.try_borrow().ok()?,
+            _ => return None,
+        };
+        let lent = Rc::new(BorrowedParent {
+            recipients: self.clone(),
+            released: Cell::new(false),
+            events: RefCell::new(vec![]),
+        });
+        let set = |observers: &Observers<IT, LT>| {
+            *observers.borrowed_parent.borrow_mut() = Rc::downgrade(&lent);
+        };
+        // A child whose own value is mutably borrowed keeps the recipients of its own guard
+        for child in node.children.iter() {
+            match child {
+                TreeElementImpl::Node(n) => {
+                    if let Ok(n) = n.try_borrow() {
+                        set(&n.observers);
+                    }
+                }
+                TreeElementImpl::Leaf(l) => {
+                    if let Ok(l) = l.try_borrow() {
+                        set(&l.observers);
+                    }
+                }
+            }
+        }
+        Some(lent)
+    }
+
+    // Records the edit in all journals and reports the event to all observers.
+    // A journal is borrowed while it replays edits, these edits are not recorded again.
+    pub fn report<E, F>(&self, make_edit: E, make_event: F)
+    where
+        E: Fn() -> Edit<IT, LT>,
+        F: FnOnce() -> TreeEvent<IT, LT>,
+    {
+        for journal in self.journals.iter() {
+            if let Ok(mut journal) = journal.try_borrow_mut() {
+                journal.record(make_edit());
+            }
+        }
+        self.notify(make_event);
+    }
+
+    // The event is only created if there is at least one observer
+    pub fn notify<F>(&self, make_event: F)
+    where
+        F: FnOnce() -> TreeEvent<IT, LT>,
+    {
+        if self.observers.is_empty() {
+            return;
+        }
+        self.forward(&self.observers, make_event());
+    }
+
+    fn forward(&self, observers: &[SharedObserver<IT, LT>], event: TreeEvent<IT, LT>) {
+        match &self.deferred_to {
+            Some(borrowed_parent) => borrowed_parent.defer(observers, event),
+            None => {
+                for observer in observers.iter() {
+                    observer.deliver(&event);
+                }
+            }
+        }
+    }
+}
+
+impl<IT, LT> Default for Recipients<IT, LT> {
+    fn default() -> Self {
+        Recipients {
+            observers: vec![],
+            journals: vec![],
+            deferred_to: None,
+        }
+    }
+}
+
+impl<IT, LT> Clone for Recipients<IT, LT> {
+    fn clone(&self) -> Self {
+        Recipients {
+            observers: self.observers.clone(),
+            journals: self.journals.clone(),
+            deferred_to: self.deferred_to.clone(),
+        }
+    }
+}
+
+type DeferredEvent<IT, LT> = (Vec<SharedObserver<IT, LT>>, TreeEvent<IT, LT>);
+
+// The recipients of a node whose value is mutably borrowed, as lent to its children. Observers
+// cannot read the node while it is borrowed, so the events of changes below the node are
+// delivered when the guard is dropped. Journals record these changes immediately.
+pub struct BorrowedParent<IT, LT> {
+    recipients: Recipients<IT, LT>,
+    released: Cell<bool>,
+    events: RefCell<Vec<DeferredEvent<IT, LT>>>,
+}
+
+impl<IT, LT> BorrowedParent<IT, LT> {
+    fn defer(&self, observers: &[SharedObserver<IT, LT>], event: TreeEvent<IT, LT>) {
+        if self.released.get() {
+            self.recipients.forward(observers, event);
+        } else {
+            self.events.borrow_mut().push((observers.to_vec(), event));
+        }
+    }
+
+    // Called by the guard after the borrow has ended
+    pub fn release(&self) {
+        self.released.set(true);
+        let events = self.events.take();
+        for (observers, event) in events {
+            self.recipients.forward(&observers, event);
+        }
+    }
+}
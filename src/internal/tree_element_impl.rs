@@ -1,7 +1,8 @@
 use crate::internal::leaf_impl::LeafImpl;
 use crate::internal::node_impl::NodeImpl;
+use crate::internal::observers::Observers;
 use crate::tree::Value;
-use std::cell::RefCell;
+use std::cell::{RefCell, RefMut};
 use std::rc::{Rc, Weak};
 
 #[derive(Debug)]
@@ -17,6 +18,19 @@ impl<IT, LT> TreeElementImpl<IT, LT> {
             Value::Leaf(l) => Self::Leaf(Rc::new(RefCell::new(LeafImpl::new(l, parent)))),
         }
     }
+    pub(crate) fn observers_mut(&self) -> RefMut<'_, Observers<IT, LT>> {
+        match self {
+            TreeElementImpl::Node(n) => RefMut::map(n.borrow_mut(), |n| &mut n.observers),
+            TreeElementImpl::Leaf(l) => RefMut::map(l.borrow_mut(), |l| &mut l.observers),
+        }
+    }
+    pub(crate) fn ptr_eq(&self, other: &TreeElementImpl<IT, LT>) -> bool {
+        match (self, other) {
+            (TreeElementImpl::Node(a), TreeElementImpl::Node(b)) => Rc::ptr_eq(a, b),
+            (TreeElementImpl::Leaf(a), TreeElementImpl::Leaf(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
     pub(crate) fn update_parent(&mut self, parent: Option<Weak<RefCell<NodeImpl<IT, LT>>>>) {
         match self {
             TreeElementImpl::Node(n) => n.borrow_mut().parent = parent,
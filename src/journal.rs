@@ -0,0 +1,517 @@
+use crate::internal::observers::WeakJournal;
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{DLTreeError, Tree, Value};
+use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::weak_tree_element::WeakTreeElement;
+use crate::tree_elements::weak_tree_element_type::WeakNode;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+
+// A recorded edit that can be reverted and applied again.
+// Parents and edited values are referenced weakly, because the journal is owned by the
+// observed element itself. Inserted, removed and replaced children are kept alive by the
+// journal, so they can be put back into the tree.
+pub(crate) enum Edit<IT, LT> {
+    Insert {
+        parent: WeakNode<IT, LT>,
+        index: usize,
+        element: TreeElementImpl<IT, LT>,
+    },
+    Remove {
+        parent: WeakNode<IT, LT>,
+        index: usize,
+        element: TreeElementImpl<IT, LT>,
+    },
+    Replace {
+        parent: WeakNode<IT, LT>,
+        index: usize,
+        old: TreeElementImpl<IT, LT>,
+        new: TreeElementImpl<IT, LT>,
+    },
+    // The order the children had before the other order has been applied
+    Reorder {
+        parent: WeakNode<IT, LT>,
+        order: Vec<TreeElementImpl<IT, LT>>,
+    },
+    // The value the element had before the other value has been applied
+    Value {
+        element: WeakTreeElement<IT, LT>,
+        value: Value<IT, LT>,
+    },
+}
+
+impl<IT, LT> Edit<IT, LT> {
+    fn revert(&mut self, journal: &WeakJournal<IT, LT>) -> Result<(), DLTreeError> {
+        match self {
+            Edit::Insert {
+                parent,
+                index,
+                element,
+            } => {
+                upgrade(parent)?.remove_child_at(*index, element)?;
+                track_detached(element, journal);
+                Ok(())
+            }
+            Edit::Remove {
+                parent,
+                index,
+                element,
+            } => upgrade(parent)?.insert_child_at(*index, element.clone()),
+            Edit::Replace {
+                parent,
+                index,
+                old,
+                new,
+            } => {
+                upgrade(parent)?.replace_child_at(*index, new, old.clone())?;
+                track_detached(new, journal);
+                Ok(())
+            }
+            Edit::Reorder { .. } | Edit::Value { .. } => self.swap(),
+        }
+    }
+
+    fn apply(&mut self, journal: &WeakJournal<IT, LT>) -> Result<(), DLTreeError> {
+        match self {
+            Edit::Insert {
+                parent,
+                index,
+                element,
+            } => upgrade(parent)?.insert_child_at(*index, element.clone()),
+            Edit::Remove {
+                parent,
+                index,
+                element,
+            } => {
+                upgrade(parent)?.remove_child_at(*index, element)?;
+                track_detached(element, journal);
+                Ok(())
+            }
+            Edit::Replace {
+                parent,
+                index,
+                old,
+                new,
+            } => {
+                upgrade(parent)?.replace_child_at(*index, old, new.clone())?;
+                track_detached(old, journal);
+                Ok(())
+            }
+            Edit::Reorder { .. } | Edit::Value { .. } => self.swap(),
+        }
+    }
+
+    // Reorders and value edits are reverted and applied by exchanging the recorded state
+    // with the current state
+    fn swap(&mut self) -> Result<(), DLTreeError> {
+        match self {
+            Edit::Reorder { parent, order } => {
+                *order = upgrade(parent)?.reorder_children(order.clone())?;
+                Ok(())
+            }
+            Edit::Value { element, value } => {
+                match (element.upgrade().ok_or(DLTreeError::StaleHandle)?, value) {
+                    (TreeElement::Node(n), Value::Node(v)) => {
+                        std::mem::swap(&mut *n.value_mut(), v)
+                    }
+                    (TreeElement::Leaf(l), Value::Leaf(v)) => {
+                        std::mem::swap(&mut *l.value_mut(), v)
+                    }
+                    _ => return Err(DLTreeError::IntegrityViolated),
+                }
+                Ok(())
+            }
+            _ => Err(DLTreeError::IntegrityViolated),
+        }
+    }
+}
+
+fn upgrade<IT, LT>(
+    parent: &WeakNode<IT, LT>,
+) -> Result<crate::tree_elements::tree_element_type::Node<IT, LT>, DLTreeError> {
+    parent.upgrade().ok_or(DLTreeError::StaleHandle)
+}
+
+// Edits of removed subtrees are recorded as well, so they are reverted together with the removal
+fn track_detached<IT, LT>(element: &TreeElementImpl<IT, LT>, journal: &WeakJournal<IT, LT>) {
+    element.observers_mut().detached_journal = Some(journal.clone());
+}
+
+struct JournalEntry<IT, LT> {
+    name: Option<String>,
+    edits: Vec<Edit<IT, LT>>,
+}
+
+struct OpenTransaction {
+    name: Option<String>,
+    // Index of the first edit of this transaction in the open edits
+    start: usize,
+}
+
+pub struct EditJournal<IT, LT> {
+    // The journal itself, as it is handed to removed elements
+    this: WeakJournal<IT, LT>,
+    // Copies the value of an element before it is changed
+    snapshot: fn(&TreeElementImpl<IT, LT>) -> Value<IT, LT>,
+    // Compares the value of an element with a copied value
+    differs: fn(&TreeElementImpl<IT, LT>, &Value<IT, LT>) -> bool,
+    limit: usize,
+    undo_stack: VecDeque<JournalEntry<IT, LT>>,
+    redo_stack: Vec<JournalEntry<IT, LT>>,
+    // Edits of the currently open transactions. Nested transactions share these edits.
+    open_edits: Vec<Edit<IT, LT>>,
+    transactions: Vec<OpenTransaction>,
+}
+
+impl<IT, LT> EditJournal<IT, LT> {
+    fn new(
+        this: WeakJournal<IT, LT>,
+        snapshot: fn(&TreeElementImpl<IT, LT>) -> Value<IT, LT>,
+        differs: fn(&TreeElementImpl<IT, LT>, &Value<IT, LT>) -> bool,
+        limit: usize,
+    ) -> Self {
+        EditJournal {
+            this,
+            snapshot,
+            differs,
+            limit,
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+            open_edits: vec![],
+            transactions: vec![],
+        }
+    }
+
+    pub(crate) fn record(&mut self, edit: Edit<IT, LT>) {
+        match &edit {
+            Edit::Remove { element, .. } => track_detached(element, &self.this),
+            Edit::Replace { old, .. } => track_detached(old, &self.this),
+            _ => {}
+        }
+        self.redo_stack.clear();
+        if self.transactions.is_empty() {
+            self.push_entry(JournalEntry {
+                name: None,
+                edits: vec![edit],
+            });
+        } else {
+            self.open_edits.push(edit);
+        }
+    }
+
+    fn push_entry(&mut self, entry: JournalEntry<IT, LT>) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.undo_stack.len() == self.limit {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(entry);
+    }
+
+    pub(crate) fn snapshot(&self, element: &TreeElementImpl<IT, LT>) -> Value<IT, LT> {
+        (self.snapshot)(element)
+    }
+
+    // Records the previous value of the element, unless the value has not been changed
+    pub(crate) fn record_value(&mut self, element: &TreeElementImpl<IT, LT>, value: Value<IT, LT>) {
+        if (self.differs)(element, &value) {
+            self.record(Edit::Value {
+                element: TreeElement::new(element).downgrade(),
+                value,
+            });
+        }
+    }
+
+    // A journal whose edits could not be replayed does not describe the tree anymore
+    fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.open_edits.clear();
+        self.transactions.clear();
+    }
+
+    pub(crate) fn begin_transaction(&mut self, name: Option<String>) {
+        self.transactions.push(OpenTransaction {
+            name,
+            start: self.open_edits.len(),
+        });
+    }
+
+    pub(crate) fn commit_transaction(&mut self) -> Result<(), DLTreeError> {
+        let transaction = self.transactions.pop().ok_or(DLTreeError::NoTransaction)?;
+        // Nested transactions become part of the outermost one
+        if self.transactions.is_empty() && !self.open_edits.is_empty() {
+            let edits = std::mem::take(&mut self.open_edits);
+            self.push_entry(JournalEntry {
+                name: transaction.name,
+                edits,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn rollback_transaction(&mut self) -> Result<(), DLTreeError> {
+        let transaction = self.transactions.pop().ok_or(DLTreeError::NoTransaction)?;
+        let edits = self.open_edits.split_off(transaction.start);
+        for mut edit in edits.into_iter().rev() {
+            if let Err(error) = edit.revert(&self.this) {
+                self.clear();
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<bool, DLTreeError> {
+        if !self.transactions.is_empty() {
+            return Err(DLTreeError::TransactionInProgress);
+        }
+        let mut entry = match self.undo_stack.pop_back() {
+            None => return Ok(false),
+            Some(entry) => entry,
+        };
+        for edit in entry.edits.iter_mut().rev() {
+            if let Err(error) = edit.revert(&self.this) {
+                self.clear();
+                return Err(error);
+            }
+        }
+        self.redo_stack.push(entry);
+        Ok(true)
+    }
+
+    fn redo(&mut self) -> Result<bool, DLTreeError> {
+        if !self.transactions.is_empty() {
+            return Err(DLTreeError::TransactionInProgress);
+        }
+        let mut entry = match self.redo_stack.pop() {
+            None => return Ok(false),
+            Some(entry) => entry,
+        };
+        for edit in entry.edits.iter_mut() {
+            if let Err(error) = edit.apply(&self.this) {
+                self.clear();
+                return Err(error);
+            }
+        }
+        self.undo_stack.push_back(entry);
+        Ok(true)
+    }
+}
+
+fn snapshot<IT: Clone, LT: Clone>(element: &TreeElementImpl<IT, LT>) -> Value<IT, LT> {
+    match element {
+        TreeElementImpl::Node(n) => Value::Node(n.borrow().value.clone()),
+        TreeElementImpl::Leaf(l) => Value::Leaf(l.borrow().value.clone()),
+    }
+}
+
+fn differs<IT: PartialEq, LT: PartialEq>(
+    element: &TreeElementImpl<IT, LT>,
+    value: &Value<IT, LT>,
+) -> bool {
+    match (element, value) {
+        (TreeElementImpl::Node(n), Value::Node(v)) => n.borrow().value != *v,
+        (TreeElementImpl::Leaf(l), Value::Leaf(v)) => l.borrow().value != *v,
+        _ => true,
+    }
+}
+
+impl<IT: Clone + PartialEq, LT: Clone + PartialEq> Tree<IT, LT> {
+    // Records all edits of this tree, so they can be undone and redone.
+    // At most limit entries are kept, the oldest entries are dropped first.
+    // An already enabled journal is replaced together with its history.
+    pub fn enable_journal(&self, limit: usize) {
+        let journal = Rc::new_cyclic(|this: &Weak<RefCell<EditJournal<IT, LT>>>| {
+            RefCell::new(EditJournal::new(this.clone(), snapshot, differs, limit))
+        });
+        self.tree.observers_mut().set_journal(Some(journal));
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    pub fn disable_journal(&self) {
+        self.tree.observers_mut().set_journal(None);
+    }
+
+    pub fn is_journal_enabled(&self) -> bool {
+        self.tree.observers_mut().journal().is_some()
+    }
+
+    // The journal stays borrowed while edits are replayed, so the replayed edits are not
+    // recorded again
+    pub(crate) fn with_journal<F, R>(&self, journal_fn: F) -> Result<R, DLTreeError>
+    where
+        F: FnOnce(&mut EditJournal<IT, LT>) -> Result<R, DLTreeError>,
+    {
+        let journal = match self.tree.observers_mut().journal() {
+            Some(journal) => journal.clone(),
+            None => return Err(DLTreeError::JournalDisabled),
+        };
+        let result = journal_fn(&mut journal.borrow_mut());
+        result
+    }
+
+    // Returns false if there is nothing to undo
+    pub fn undo(&self) -> Result<bool, DLTreeError> {
+        self.with_journal(|journal| journal.undo())
+    }
+
+    // Returns false if there is nothing to redo
+    pub fn redo(&self) -> Result<bool, DLTreeError> {
+        self.with_journal(|journal| journal.redo())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.with_journal(|journal| Ok(!journal.undo_stack.is_empty()))
+            .unwrap_or(false)
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.with_journal(|journal| Ok(!journal.redo_stack.is_empty()))
+            .unwrap_or(false)
+    }
+
+    // Name of the transaction that is undone next. Single edits have no name.
+    pub fn undo_name(&self) -> Option<String> {
+        self.with_journal(|journal| Ok(journal.undo_stack.back().and_then(|e| e.name.clone())))
+            .unwrap_or(None)
+    }
+
+    pub fn redo_name(&self) -> Option<String> {
+        self.with_journal(|journal| Ok(journal.redo_stack.last().and_then(|e| e.name.clone())))
+            .unwrap_or(None)
+    }
+
+    // All edits until the matching commit are undone and redone as a single step.
+    // Transactions can be nested, the nested ones become part of the outermost one.
+    pub fn begin_transaction(&self, name: &str) -> Result<(), DLTreeError> {
+        self.with_journal(|journal| {
+            journal.begin_transaction(Some(name.to_string()));
+            Ok(())
+        })
+    }
+
+    pub fn commit_transaction(&self) -> Result<(), DLTreeError> {
+        self.with_journal(|journal| journal.commit_transaction())
+    }
+
+    // Reverts all edits of the innermost open transaction
+    pub fn rollback_transaction(&self) -> Result<(), DLTreeError> {
+        self.with_journal(|journal| journal.rollback_transaction())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::describe;
+    use crate::tree::{DLTreeError, Tree, Value};
+
+    #[test]
+    fn undo_redo_test() -> Result<(), DLTreeError> {
+        let tree = crate::tree!(node 1 => [leaf 2, node 3 => [leaf 4], leaf 5]);
+        tree.enable_journal(100);
+        let mut root = tree.root_node().as_node().unwrap();
+        let children = root.children();
+        let mut node3 = children[1].as_node().unwrap();
+        let mut leaf5 = children[2].clone();
+
+        node3.push_front_child(Value::Node(6));
+        leaf5.remove_from_tree()?;
+        *children[0].as_leaf().unwrap().value_mut() = 7;
+        // Guards that leave the value unchanged are not recorded
+        assert_eq!(*root.value_mut(), 1);
+        *node3.value_mut() = 3;
+        root.sort_children_unstable(|a, b| a.value().cmp(&b.value()));
+        let mut replaced = children[0].as_leaf().unwrap().set_node(8)?;
+        replaced.insert_after(Value::Leaf(9))?;
+        let modified = "1(3(6(),4),8(),9)";
+        assert_eq!(describe(&tree.root_node()), modified);
+
+        let mut states = vec![];
+        while tree.undo()? {
+            states.push(describe(&tree.root_node()));
+        }
+        assert_eq!(
+            states,
+            vec![
+                "1(3(6(),4),8())",
+                "1(3(6(),4),7)",
+                "1(7,3(6(),4))",
+                "1(2,3(6(),4))",
+                "1(2,3(6(),4),5)",
+                "1(2,3(4),5)",
+            ]
+        );
+        assert!(!tree.can_undo());
+        assert_eq!(leaf5.parent()?.unwrap(), root);
+        assert_eq!(node3.parent()?.unwrap(), root);
+
+        while tree.redo()? {}
+        assert_eq!(describe(&tree.root_node()), modified);
+        assert!(leaf5.parent()?.is_none());
+        assert!(children[0].parent()?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn transaction_test() -> Result<(), DLTreeError> {
+        let tree = crate::tree!(node 1 => [leaf 2]);
+        assert_eq!(tree.undo(), Err(DLTreeError::JournalDisabled));
+        tree.enable_journal(100);
+        let mut root = tree.root_node().as_node().unwrap();
+
+        tree.begin_transaction("fill")?;
+        root.push_back_child(Value::Leaf(3));
+        tree.begin_transaction("inner")?;
+        root.push_back_child(Value::Leaf(4));
+        tree.commit_transaction()?;
+        assert_eq!(tree.undo(), Err(DLTreeError::TransactionInProgress));
+        root.push_back_child(Value::Leaf(5));
+        tree.commit_transaction()?;
+        assert_eq!(tree.commit_transaction(), Err(DLTreeError::NoTransaction));
+        assert_eq!(tree.undo_name(), Some("fill".to_string()));
+
+        tree.undo()?;
+        assert_eq!(describe(&tree.root_node()), "1(2)");
+        assert_eq!(tree.redo_name(), Some("fill".to_string()));
+        tree.redo()?;
+        assert_eq!(describe(&tree.root_node()), "1(2,3,4,5)");
+
+        tree.begin_transaction("discarded")?;
+        root.push_front_child(Value::Leaf(6));
+        *root.value_mut() = 7;
+        tree.rollback_transaction()?;
+        assert_eq!(describe(&tree.root_node()), "1(2,3,4,5)");
+        assert_eq!(tree.undo_name(), Some("fill".to_string()));
+
+        // A new edit discards the edits that could have been redone
+        tree.undo()?;
+        root.push_back_child(Value::Leaf(8));
+        assert!(!tree.can_redo());
+        assert!(!tree.redo()?);
+        Ok(())
+    }
+
+    #[test]
+    fn history_limit_test() -> Result<(), DLTreeError> {
+        let tree = Tree::new(Value::Node(0));
+        tree.enable_journal(2);
+        let mut root = tree.root_node().as_node().unwrap();
+        for i in 1..=4 {
+            root.push_back_child(Value::Leaf(i));
+        }
+        assert!(tree.undo()?);
+        assert!(tree.undo()?);
+        assert!(!tree.undo()?);
+        assert_eq!(describe(&tree.root_node()), "0(1,2)");
+
+        tree.disable_journal();
+        assert!(!tree.is_journal_enabled());
+        root.push_back_child(Value::Leaf(5));
+        assert_eq!(tree.undo(), Err(DLTreeError::JournalDisabled));
+        Ok(())
+    }
+}
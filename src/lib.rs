@@ -1,6 +1,28 @@
+pub mod aggregate;
+pub mod ancestry;
+pub mod arena;
+pub mod builder;
+pub mod cursor;
+pub mod encodings;
+pub mod filesystem;
+pub mod filter;
+pub mod frozen;
 mod internal;
+pub mod journal;
+mod macros;
+pub mod metrics;
+pub mod observer;
+pub mod persistent;
+pub mod restructure;
+pub mod search;
+pub mod selector;
+pub mod sync;
+#[cfg(test)]
+mod test_util;
+pub mod transaction;
 pub mod tree;
 pub mod tree_elements;
+pub mod visitor;
 
 pub trait DeepClone {
     fn deep_clone(&self) -> Self;
@@ -0,0 +1,34 @@
+// Builds a tree from a declarative description, e.g.
+// tree!(node 1 => [leaf 2, node 3 => [leaf 4]])
+#[macro_export]
+macro_rules! tree {
+    (node $value:expr => [ $($children:tt)* ]) => {
+        $crate::__tree_children!($crate::builder::TreeBuilder::new($value); $($children)*).build()
+    };
+    (node $value:expr) => {
+        $crate::tree::Tree::new($crate::tree::Value::Node($value))
+    };
+    (leaf $value:expr) => {
+        $crate::tree::Tree::new($crate::tree::Value::Leaf($value))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tree_children {
+    ($builder:expr;) => {
+        $builder
+    };
+    ($builder:expr; node $value:expr => [ $($children:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::__tree_children!(
+            $builder.node($value, |builder| $crate::__tree_children!(builder; $($children)*));
+            $($($rest)*)?
+        )
+    };
+    ($builder:expr; node $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__tree_children!($builder.node($value, |builder| builder); $($($rest)*)?)
+    };
+    ($builder:expr; leaf $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__tree_children!($builder.leaf($value); $($($rest)*)?)
+    };
+}
@@ -0,0 +1,179 @@
+use crate::internal::metrics::{self, SubtreeMetrics};
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::Tree;
+use crate::tree_elements::tree_element_type::Node;
+
+// Without an enabled subtree cache every query walks the whole subtree
+impl<IT, LT> Node<IT, LT> {
+    fn metrics(&self) -> SubtreeMetrics {
+        let cached = self.element_impl.borrow().metrics.get();
+        cached.unwrap_or_else(|| metrics::measure(&self.to_impl()))
+    }
+
+    // The number of elements in the subtree, the node itself included
+    pub fn subtree_len(&self) -> usize {
+        self.metrics().len
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.metrics().leaves
+    }
+
+    // The number of edges on the longest path down from this node, 0 for a node without children
+    pub fn height(&self) -> usize {
+        self.metrics().height
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    // Stores subtree_len, leaf_count and height in every node and updates them on each change
+    // of the tree. Subtrees that are removed from the tree keep their cache.
+    // Has no effect on a tree that consists of a single leaf.
+    pub fn enable_subtree_cache(&self) {
+        if let TreeElementImpl::Node(n) = &self.tree {
+            metrics::compute(n);
+        }
+    }
+
+    pub fn disable_subtree_cache(&self) {
+        if let TreeElementImpl::Node(n) = &self.tree {
+            metrics::clear(n);
+        }
+    }
+
+    pub fn is_subtree_cache_enabled(&self) -> bool {
+        match &self.tree {
+            TreeElementImpl::Node(n) => n.borrow().metrics.get().is_some(),
+            TreeElementImpl::Leaf(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::internal::metrics;
+    use crate::search::SearchOrder;
+    use crate::test_util::{build_tree, child};
+    use crate::tree::{DLTreeError, Tree, Value};
+    use crate::tree_elements::tree_element::TreeElement;
+
+    // Compares the cached metrics of every node with a walk of its subtree
+    fn check(tree: &Tree<String, u32>) {
+        for element in tree.root_node().traverse(SearchOrder::DepthFirst) {
+            if let TreeElement::Node(n) = element {
+                let cached = n.element_impl.borrow().metrics.get();
+                assert_eq!(cached, Some(metrics::measure(&n.to_impl())));
+            }
+        }
+    }
+
+    #[test]
+    fn uncached_test() {
+        let tree = build_tree();
+        let a = tree.root_node().as_node().unwrap();
+        assert!(!tree.is_subtree_cache_enabled());
+        assert_eq!((a.subtree_len(), a.leaf_count(), a.height()), (10, 5, 3));
+        let d = child(&tree.root_node(), 2).as_node().unwrap();
+        assert_eq!((d.subtree_len(), d.leaf_count(), d.height()), (3, 1, 1));
+    }
+
+    #[test]
+    fn cached_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        tree.enable_subtree_cache();
+        assert!(tree.is_subtree_cache_enabled());
+        check(&tree);
+        let root = tree.root_node();
+        let mut a = root.as_node().unwrap();
+
+        // Removing the highest subtree lowers the height
+        let mut b = child(&root, 1);
+        let mut removed = b.remove_from_tree()?;
+        check(&tree);
+        assert_eq!((a.subtree_len(), a.leaf_count(), a.height()), (6, 3, 2));
+
+        // The removed subtree keeps its cache, also while it is changed
+        let mut c = child(&removed.root_node(), 1).as_node().unwrap();
+        c.push_back_child(Value::Node("e".to_string()))
+            .as_node()
+            .unwrap()
+            .push_front_child(Value::Leaf(5));
+        check(&removed);
+        a.push_child_tree(removed.root_node());
+        check(&tree);
+        assert_eq!((a.subtree_len(), a.leaf_count(), a.height()), (12, 6, 4));
+
+        let mut four = child(&child(&root, 1), 0).as_leaf().unwrap();
+        four.insert_before(Value::Node("f".to_string()))?;
+        four.insert_after(Value::Leaf(6))?;
+        check(&tree);
+        four.set_node("g".to_string())?;
+        check(&tree);
+        child(&root, 0).set(Value::Node("h".to_string()))?;
+        check(&tree);
+
+        // A subtree without cache gets a cache when it is inserted
+        removed = Tree::new(Value::Node("i".to_string()));
+        removed
+            .root_node()
+            .as_node()
+            .unwrap()
+            .push_back_child(Value::Leaf(7));
+        a.push_child_tree(removed.root_node());
+        check(&tree);
+
+        c.remove_all_children()?;
+        a.sort_children_unstable(|x, y| y.as_leaf().is_some().cmp(&x.as_leaf().is_some()));
+        check(&tree);
+        assert_eq!(a.height(), 2);
+
+        // The cache is updated while the value of an ancestor is read
+        let value = a.value();
+        c.push_back_child(Value::Leaf(8));
+        drop(value);
+        check(&tree);
+
+        // Changes below a mutably borrowed value update the cache when the borrow ends
+        let guard = a.value_mut();
+        c.push_back_child(Value::Node("j".to_string()))
+            .as_node()
+            .unwrap()
+            .push_back_child(Value::Leaf(9));
+        drop(guard);
+        check(&tree);
+        let mut parent = c.parent()?.unwrap();
+        let guard = c.value_mut();
+        parent.push_back_child(Value::Leaf(10));
+        parent.children()[0].clone().remove_from_tree()?;
+        drop(guard);
+        check(&tree);
+
+        tree.disable_subtree_cache();
+        assert!(!tree.is_subtree_cache_enabled());
+        assert_eq!(a.subtree_len(), metrics::measure(&a.to_impl()).len);
+        Ok(())
+    }
+
+    #[test]
+    fn journal_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        tree.enable_subtree_cache();
+        tree.enable_journal(10);
+        let root = tree.root_node();
+        child(&root, 1).remove_from_tree()?;
+        child(&root, 1)
+            .as_node()
+            .unwrap()
+            .push_back_child(Value::Leaf(5));
+        check(&tree);
+        while tree.undo()? {
+            check(&tree);
+        }
+        assert_eq!(root.as_node().unwrap().subtree_len(), 10);
+        while tree.redo()? {
+            check(&tree);
+        }
+        assert_eq!(root.as_node().unwrap().subtree_len(), 7);
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+use crate::internal::observers::ObserverCell;
+use crate::tree::Tree;
+use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::tree_element_type::Node;
+use std::rc::Rc;
+
+// Events are delivered synchronously after the mutation has been applied.
+// Indexes refer to the children of the parent directly after the mutation.
+#[derive(Debug)]
+pub enum TreeEvent<IT, LT> {
+    ChildInserted {
+        parent: Node<IT, LT>,
+        index: usize,
+    },
+    ChildRemoved {
+        parent: Node<IT, LT>,
+        index: usize,
+        removed: TreeElement<IT, LT>,
+    },
+    ElementReplaced {
+        parent: Node<IT, LT>,
+        index: usize,
+        old: TreeElement<IT, LT>,
+        new: TreeElement<IT, LT>,
+    },
+    ChildrenReordered {
+        parent: Node<IT, LT>,
+        previous_order: Vec<TreeElement<IT, LT>>,
+    },
+    // Reported when the guard returned by value_mut is dropped
+    ValueChanged {
+        element: TreeElement<IT, LT>,
+    },
+}
+
+impl<IT, LT> Clone for TreeEvent<IT, LT> {
+    fn clone(&self) -> Self {
+        match self {
+            TreeEvent::ChildInserted { parent, index } => TreeEvent::ChildInserted {
+                parent: parent.clone(),
+                index: *index,
+            },
+            TreeEvent::ChildRemoved {
+                parent,
+                index,
+                removed,
+            } => TreeEvent::ChildRemoved {
+                parent: parent.clone(),
+                index: *index,
+                removed: removed.clone(),
+            },
+            TreeEvent::ElementReplaced {
+                parent,
+                index,
+                old,
+                new,
+            } => TreeEvent::ElementReplaced {
+                parent: parent.clone(),
+                index: *index,
+                old: old.clone(),
+                new: new.clone(),
+            },
+            TreeEvent::ChildrenReordered {
+                parent,
+                previous_order,
+            } => TreeEvent::ChildrenReordered {
+                parent: parent.clone(),
+                previous_order: previous_order.clone(),
+            },
+            TreeEvent::ValueChanged { element } => TreeEvent::ValueChanged {
+                element: element.clone(),
+            },
+        }
+    }
+}
+
+pub trait TreeObserver<IT, LT> {
+    fn on_event(&mut self, event: &TreeEvent<IT, LT>);
+}
+
+impl<IT, LT, F> TreeObserver<IT, LT> for F
+where
+    F: FnMut(&TreeEvent<IT, LT>),
+{
+    fn on_event(&mut self, event: &TreeEvent<IT, LT>) {
+        self(event)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(pub(crate) u64);
+
+impl<IT, LT> Tree<IT, LT> {
+    // The observer receives all events of this tree, i.e. of the element this tree has been
+    // created from and of all of its descendants.
+    pub fn add_observer<O>(&self, observer: O) -> ObserverId
+    where
+        O: TreeObserver<IT, LT> + 'static,
+    {
+        self.tree
+            .observers_mut()
+            .add(Rc::new(ObserverCell::new(observer)))
+    }
+
+    pub fn remove_observer(&self, id: ObserverId) -> bool {
+        self.tree.observers_mut().remove(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::observer::TreeEvent;
+    use crate::tree::{DLTreeError, Tree, Value};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn record(tree: &Tree<i32, i32>) -> Rc<RefCell<Vec<String>>> {
+        let events = Rc::new(RefCell::new(vec![]));
+        let recorded = events.clone();
+        tree.add_observer(move |event: &TreeEvent<i32, i32>| {
+            let description = match event {
+                TreeEvent::ChildInserted { parent, index } => {
+                    format!("inserted {} at {}", *parent.value(), index)
+                }
+                TreeEvent::ChildRemoved {
+                    parent,
+                    index,
+                    removed,
+                } => format!(
+                    "removed {} from {} at {}",
+                    *removed.value(),
+                    *parent.value(),
+                    index
+                ),
+                TreeEvent::ElementReplaced {
+                    parent,
+                    index,
+                    old,
+                    new,
+                } => format!(
+                    "replaced {} by {} in {} at {}",
+                    *old.value(),
+                    *new.value(),
+                    *parent.value(),
+                    index
+                ),
+                TreeEvent::ChildrenReordered {
+                    parent,
+                    previous_order,
+                } => format!(
+                    "reordered {} from {:?}",
+                    *parent.value(),
+                    previous_order
+                        .iter()
+                        .map(|c| *c.value())
+                        .collect::<Vec<i32>>()
+                ),
+                TreeEvent::ValueChanged { element } => format!("changed {}", *element.value()),
+            };
+            recorded.borrow_mut().push(description);
+        });
+        events
+    }
+
+    #[test]
+    fn mutation_events_test() -> Result<(), DLTreeError> {
+        let tree = Tree::new(Value::Node(1));
+        let events = record(&tree);
+        let mut root = tree.root_node().as_node().unwrap();
+        let mut leaf = root.push_back_child(Value::Leaf(2)).as_leaf().unwrap();
+        root.push_front_child(Value::Leaf(3));
+        let mut node = leaf.insert_after(Value::Node(4))?.root_node();
+        leaf.insert_before(Value::Leaf(5))?;
+        *leaf.value_mut() = 6;
+        let mut node = node.set(Value::Leaf(7))?;
+        root.sort_children_unstable(|a, b| a.value().cmp(&b.value()));
+        node.remove_from_tree()?;
+        root.push_child_tree(Tree::new(Value::Leaf(8)).root_node());
+        root.remove_all_children()?;
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "inserted 1 at 0",
+                "inserted 1 at 0",
+                "inserted 1 at 2",
+                "inserted 1 at 1",
+                "changed 6",
+                "replaced 4 by 7 in 1 at 3",
+                "reordered 1 from [3, 5, 6, 7]",
+                "removed 7 from 1 at 3",
+                "inserted 1 at 3",
+                "removed 8 from 1 at 3",
+                "removed 6 from 1 at 2",
+                "removed 5 from 1 at 1",
+                "removed 3 from 1 at 0",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn subtree_observer_test() -> Result<(), DLTreeError> {
+        let tree = Tree::new(Value::Node(1));
+        let mut root = tree.root_node().as_node().unwrap();
+        let mut subtree = root.push_back_child(Value::Node(2)).as_node().unwrap();
+        let sibling = root.push_back_child(Value::Leaf(3)).as_leaf().unwrap();
+        let subtree_events = record(&subtree.clone().remove_from_tree()?);
+        root.push_child_tree(crate::tree_elements::tree_element::TreeElement::Node(
+            subtree.clone(),
+        ));
+        let root_events = record(&tree);
+
+        subtree.push_back_child(Value::Leaf(4));
+        *sibling.value_mut() = 5;
+        assert_eq!(*subtree_events.borrow(), vec!["inserted 2 at 0"]);
+        assert_eq!(*root_events.borrow(), vec!["inserted 2 at 0", "changed 5"]);
+
+        // Changes below a borrowed value are reported when the borrow ends
+        let mut guard = root.value_mut();
+        subtree.push_back_child(Value::Leaf(6));
+        let mut four = subtree.children()[0].clone();
+        four.set(Value::Leaf(7))?;
+        *guard = 8;
+        assert_eq!(subtree_events.borrow().len(), 1);
+        assert_eq!(root_events.borrow().len(), 2);
+        drop(guard);
+        assert_eq!(
+            *subtree_events.borrow(),
+            vec![
+                "inserted 2 at 0",
+                "inserted 2 at 1",
+                "replaced 4 by 7 in 2 at 0"
+            ]
+        );
+        assert_eq!(
+            root_events.borrow()[2..],
+            ["inserted 2 at 1", "replaced 4 by 7 in 2 at 0", "changed 8"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unobserved_tree_test() -> Result<(), DLTreeError> {
+        let observed = Tree::new(Value::Node(1));
+        let mut observed_root = observed.root_node().as_node().unwrap();
+        let mut observed_child = observed_root
+            .push_back_child(Value::Node(2))
+            .as_node()
+            .unwrap();
+        let events = record(&observed);
+        let tree = Tree::new(Value::Node(1));
+        let mut root = tree.root_node().as_node().unwrap();
+        let mut child = root.push_back_child(Value::Node(2)).as_node().unwrap();
+
+        // Observing one tree does not affect changes below borrowed values of another tree
+        let observed_guard = observed_root.value_mut();
+        let guard = root.value_mut();
+        child.push_back_child(Value::Leaf(3));
+        *child.value_mut() = 4;
+        child.children()[0].clone().set(Value::Leaf(5))?;
+        drop(guard);
+        assert_eq!(*child.value(), 4);
+        assert_eq!(*child.children()[0].as_leaf().unwrap().value(), 5);
+
+        observed_child.push_back_child(Value::Leaf(3));
+        assert!(events.borrow().is_empty());
+        drop(observed_guard);
+        assert_eq!(*events.borrow(), vec!["inserted 2 at 0", "changed 1"]);
+        root.push_back_child(Value::Leaf(6));
+        observed_root.push_back_child(Value::Leaf(6));
+        assert_eq!(events.borrow().len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn reentrant_observer_test() {
+        let tree = Tree::new(Value::Node(1));
+        let root = tree.root_node().as_node().unwrap();
+        let weak_root = root.downgrade();
+        let indexes = Rc::new(RefCell::new(vec![]));
+        let recorded = indexes.clone();
+        // The observer fills the root up to three children. The events of its own insertions
+        // are delivered after it has returned.
+        tree.add_observer(move |event: &TreeEvent<i32, i32>| {
+            if let TreeEvent::ChildInserted { index, .. } = event {
+                recorded.borrow_mut().push(*index);
+                if *index < 2 {
+                    weak_root.upgrade().unwrap().push_back_child(Value::Leaf(0));
+                }
+            }
+        });
+        root.clone().push_back_child(Value::Leaf(0));
+        assert_eq!(*indexes.borrow(), vec![0, 1, 2]);
+        assert_eq!(root.children().len(), 3);
+    }
+
+    #[test]
+    fn remove_observer_test() {
+        let tree = Tree::new(Value::Leaf(1));
+        let events = Rc::new(RefCell::new(0));
+        let counter = events.clone();
+        let id = tree.add_observer(move |_: &TreeEvent<i32, i32>| *counter.borrow_mut() += 1);
+        *tree.root_node().as_leaf().unwrap().value_mut() = 2;
+        assert!(tree.remove_observer(id));
+        assert!(!tree.remove_observer(id));
+        *tree.root_node().as_leaf().unwrap().value_mut() = 3;
+        assert_eq!(*events.borrow(), 1);
+    }
+}
@@ -0,0 +1,531 @@
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree::{DLTreeError, Tree, Value};
+use crate::tree_elements::tree_element::TreeElement;
+use std::rc::Rc;
+
+#[derive(Debug)]
+struct PNodeData<IT, LT> {
+    value: Rc<IT>,
+    children: Vec<PElement<IT, LT>>,
+}
+
+// Elements are immutable and shared between all versions that contain them.
+// There are no parent pointers, navigation upwards is done with a PZipper.
+#[derive(Debug)]
+pub struct PNode<IT, LT> {
+    data: Rc<PNodeData<IT, LT>>,
+}
+
+#[derive(Debug)]
+pub enum PElement<IT, LT> {
+    Node(PNode<IT, LT>),
+    Leaf(Rc<LT>),
+}
+
+#[derive(Debug)]
+pub struct PTree<IT, LT> {
+    root: PElement<IT, LT>,
+}
+
+// A position in a persistent tree together with the path of ancestors leading to it.
+// Edits at the position only copy the ancestors when the zipper is moved upwards.
+#[derive(Debug)]
+pub struct PZipper<IT, LT> {
+    ancestors: Vec<(PNode<IT, LT>, usize)>,
+    focus: PElement<IT, LT>,
+}
+
+impl<IT, LT> PNode<IT, LT> {
+    fn new(value: Rc<IT>, children: Vec<PElement<IT, LT>>) -> Self {
+        PNode {
+            data: Rc::new(PNodeData { value, children }),
+        }
+    }
+
+    // Copies this node with the given children. The value is shared with this node.
+    fn with_children(&self, children: Vec<PElement<IT, LT>>) -> Self {
+        PNode::new(self.data.value.clone(), children)
+    }
+
+    pub fn value(&self) -> &IT {
+        &self.data.value
+    }
+
+    pub fn children(&self) -> &[PElement<IT, LT>] {
+        &self.data.children
+    }
+
+    pub fn child(&self, index: usize) -> Option<&PElement<IT, LT>> {
+        self.data.children.get(index)
+    }
+
+    pub fn ptr_eq(&self, other: &PNode<IT, LT>) -> bool {
+        Rc::ptr_eq(&self.data, &other.data)
+    }
+}
+
+impl<IT, LT> Clone for PNode<IT, LT> {
+    fn clone(&self) -> Self {
+        PNode {
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<IT, LT> PElement<IT, LT> {
+    pub fn new(value: Value<IT, LT>) -> Self {
+        match value {
+            Value::Node(n) => PElement::Node(PNode::new(Rc::new(n), vec![])),
+            Value::Leaf(l) => PElement::Leaf(Rc::new(l)),
+        }
+    }
+
+    pub fn value(&self) -> Value<&IT, &LT> {
+        match self {
+            PElement::Node(n) => Value::Node(n.value()),
+            PElement::Leaf(l) => Value::Leaf(l),
+        }
+    }
+
+    pub fn as_node(&self) -> Option<&PNode<IT, LT>> {
+        match self {
+            PElement::Node(n) => Some(n),
+            PElement::Leaf(_) => None,
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.as_node().is_none()
+    }
+
+    // Returns true if both elements are the same shared element
+    pub fn ptr_eq(&self, other: &PElement<IT, LT>) -> bool {
+        match (self, other) {
+            (PElement::Node(a), PElement::Node(b)) => a.ptr_eq(b),
+            (PElement::Leaf(a), PElement::Leaf(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl<IT, LT> Clone for PElement<IT, LT> {
+    fn clone(&self) -> Self {
+        match self {
+            PElement::Node(n) => PElement::Node(n.clone()),
+            PElement::Leaf(l) => PElement::Leaf(l.clone()),
+        }
+    }
+}
+
+impl<IT, LT> PTree<IT, LT> {
+    pub fn new(root_value: Value<IT, LT>) -> Self {
+        PTree {
+            root: PElement::new(root_value),
+        }
+    }
+
+    pub fn root(&self) -> &PElement<IT, LT> {
+        &self.root
+    }
+
+    // A path contains the child index for every level below the root.
+    // The empty path refers to the root.
+    pub fn get(&self, path: &[usize]) -> Option<&PElement<IT, LT>> {
+        let mut element = &self.root;
+        for index in path {
+            element = element.as_node()?.child(*index)?;
+        }
+        Some(element)
+    }
+
+    pub fn zipper(&self) -> PZipper<IT, LT> {
+        PZipper {
+            ancestors: vec![],
+            focus: self.root.clone(),
+        }
+    }
+
+    pub fn zipper_at(&self, path: &[usize]) -> Result<PZipper<IT, LT>, DLTreeError> {
+        let mut zipper = self.zipper();
+        for index in path {
+            zipper.down_checked(*index)?;
+        }
+        Ok(zipper)
+    }
+
+    // Returns true if both versions share the same root, i.e. no edit happened in between
+    pub fn ptr_eq(&self, other: &PTree<IT, LT>) -> bool {
+        self.root.ptr_eq(&other.root)
+    }
+
+    // The following edits return a new version and leave this version unchanged
+
+    pub fn set(&self, path: &[usize], value: Value<IT, LT>) -> Result<PTree<IT, LT>, DLTreeError> {
+        let mut zipper = self.zipper_at(path)?;
+        zipper.set(value);
+        Ok(zipper.into_tree())
+    }
+
+    // Replaces the value of the element at path. The children of a node are kept.
+    pub fn set_value(
+        &self,
+        path: &[usize],
+        value: Value<IT, LT>,
+    ) -> Result<PTree<IT, LT>, DLTreeError> {
+        let mut zipper = self.zipper_at(path)?;
+        zipper.set_value(value)?;
+        Ok(zipper.into_tree())
+    }
+
+    // Inserts the new element so that it can be found at path afterwards
+    pub fn insert(
+        &self,
+        path: &[usize],
+        value: Value<IT, LT>,
+    ) -> Result<PTree<IT, LT>, DLTreeError> {
+        let (index, parent_path) = path
+            .split_last()
+            .ok_or(DLTreeError::ChildOperationOnRootLevel)?;
+        let mut zipper = self.zipper_at(parent_path)?;
+        zipper.insert_child(*index, value)?;
+        Ok(zipper.into_tree())
+    }
+
+    pub fn push_child(
+        &self,
+        path: &[usize],
+        value: Value<IT, LT>,
+    ) -> Result<PTree<IT, LT>, DLTreeError> {
+        let mut zipper = self.zipper_at(path)?;
+        zipper.push_child(value)?;
+        Ok(zipper.into_tree())
+    }
+
+    pub fn remove(&self, path: &[usize]) -> Result<PTree<IT, LT>, DLTreeError> {
+        let mut zipper = self.zipper_at(path)?;
+        zipper.remove()?;
+        Ok(zipper.into_tree())
+    }
+}
+
+impl<IT, LT> Clone for PTree<IT, LT> {
+    fn clone(&self) -> Self {
+        PTree {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<IT, LT> PZipper<IT, LT> {
+    pub fn focus(&self) -> &PElement<IT, LT> {
+        &self.focus
+    }
+
+    pub fn path(&self) -> Vec<usize> {
+        self.ancestors.iter().map(|(_, index)| *index).collect()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.ancestors.len()
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.ancestors.is_empty()
+    }
+
+    fn down_checked(&mut self, index: usize) -> Result<(), DLTreeError> {
+        let node = self
+            .focus
+            .as_node()
+            .ok_or(DLTreeError::ElementKindMismatch)?
+            .clone();
+        let child = node
+            .child(index)
+            .ok_or(DLTreeError::IndexOutOfRange)?
+            .clone();
+        self.ancestors.push((node, index));
+        self.focus = child;
+        Ok(())
+    }
+
+    // Moves to the child at index. Returns false if there is no such child.
+    pub fn down(&mut self, index: usize) -> bool {
+        self.down_checked(index).is_ok()
+    }
+
+    // Moves to the parent and copies it if the focused element has been edited.
+    // Returns false at the root.
+    pub fn up(&mut self) -> bool {
+        let (parent, index) = match self.ancestors.pop() {
+            None => return false,
+            Some(ancestor) => ancestor,
+        };
+        let focus = std::mem::replace(&mut self.focus, PElement::Node(parent.clone()));
+        if !parent.data.children[index].ptr_eq(&focus) {
+            let mut children = parent.data.children.clone();
+            children[index] = focus;
+            self.focus = PElement::Node(parent.with_children(children));
+        }
+        true
+    }
+
+    fn sibling(&mut self, forward: bool) -> bool {
+        let index = match self.ancestors.last() {
+            Some((parent, index)) if forward && index + 1 < parent.children().len() => index + 1,
+            Some((_, index)) if !forward && *index > 0 => index - 1,
+            _ => return false,
+        };
+        self.up();
+        self.down(index)
+    }
+
+    pub fn next_sibling(&mut self) -> bool {
+        self.sibling(true)
+    }
+
+    pub fn prev_sibling(&mut self) -> bool {
+        self.sibling(false)
+    }
+
+    // Replaces the focused element including its children
+    pub fn set(&mut self, value: Value<IT, LT>) {
+        self.focus = PElement::new(value);
+    }
+
+    pub fn replace(&mut self, element: PElement<IT, LT>) {
+        self.focus = element;
+    }
+
+    // Replaces the value of the focused element. The children of a node are kept.
+    pub fn set_value(&mut self, value: Value<IT, LT>) -> Result<(), DLTreeError> {
+        self.focus = match (&self.focus, value) {
+            (PElement::Node(n), Value::Node(value)) => {
+                PElement::Node(PNode::new(Rc::new(value), n.data.children.clone()))
+            }
+            (PElement::Leaf(_), Value::Leaf(value)) => PElement::Leaf(Rc::new(value)),
+            _ => return Err(DLTreeError::ElementKindMismatch),
+        };
+        Ok(())
+    }
+
+    fn update_children<F>(&mut self, update_fn: F) -> Result<(), DLTreeError>
+    where
+        F: FnOnce(&mut Vec<PElement<IT, LT>>) -> Result<(), DLTreeError>,
+    {
+        let node = self
+            .focus
+            .as_node()
+            .ok_or(DLTreeError::ElementKindMismatch)?;
+        let mut children = node.data.children.clone();
+        update_fn(&mut children)?;
+        self.focus = PElement::Node(node.with_children(children));
+        Ok(())
+    }
+
+    pub fn insert_child(&mut self, index: usize, value: Value<IT, LT>) -> Result<(), DLTreeError> {
+        self.update_children(|children| {
+            if index > children.len() {
+                return Err(DLTreeError::IndexOutOfRange);
+            }
+            children.insert(index, PElement::new(value));
+            Ok(())
+        })
+    }
+
+    pub fn push_child(&mut self, value: Value<IT, LT>) -> Result<(), DLTreeError> {
+        self.update_children(|children| {
+            children.push(PElement::new(value));
+            Ok(())
+        })
+    }
+
+    pub fn remove_child(&mut self, index: usize) -> Result<PElement<IT, LT>, DLTreeError> {
+        let mut removed = None;
+        self.update_children(|children| {
+            if index >= children.len() {
+                return Err(DLTreeError::IndexOutOfRange);
+            }
+            removed = Some(children.remove(index));
+            Ok(())
+        })?;
+        removed.ok_or(DLTreeError::IntegrityViolated)
+    }
+
+    // Removes the focused element. The zipper moves to the parent afterwards.
+    pub fn remove(&mut self) -> Result<PElement<IT, LT>, DLTreeError> {
+        let (parent, index) = self
+            .ancestors
+            .pop()
+            .ok_or(DLTreeError::ChildOperationOnRootLevel)?;
+        let removed = std::mem::replace(&mut self.focus, PElement::Node(parent));
+        self.remove_child(index)?;
+        Ok(removed)
+    }
+
+    // Returns the version containing all edits of this zipper
+    pub fn into_tree(mut self) -> PTree<IT, LT> {
+        while self.up() {}
+        PTree { root: self.focus }
+    }
+}
+
+impl<IT: Clone, LT: Clone> PTree<IT, LT> {
+    pub fn from_tree(tree: &Tree<IT, LT>) -> Self {
+        // The elements are collected in pre-order and built in reverse order,
+        // so all children of a node are built before the node itself
+        let mut elements: Vec<(TreeElementImpl<IT, LT>, Option<usize>)> = vec![];
+        let mut stack = vec![(tree.tree.clone(), None)];
+        while let Some((element, parent)) = stack.pop() {
+            let index = elements.len();
+            if let TreeElementImpl::Node(n) = &element {
+                stack.extend(
+                    n.borrow()
+                        .children
+                        .iter()
+                        .rev()
+                        .map(|c| (c.clone(), Some(index))),
+                );
+            }
+            elements.push((element, parent));
+        }
+        let mut children: Vec<Vec<PElement<IT, LT>>> = elements.iter().map(|_| vec![]).collect();
+        let mut root = None;
+        for (index, (element, parent)) in elements.iter().enumerate().rev() {
+            let built = match element {
+                TreeElementImpl::Node(n) => {
+                    let mut node_children = std::mem::take(&mut children[index]);
+                    node_children.reverse();
+                    PElement::Node(PNode::new(Rc::new(n.borrow().value.clone()), node_children))
+                }
+                TreeElementImpl::Leaf(l) => PElement::Leaf(Rc::new(l.borrow().value.clone())),
+            };
+            match parent {
+                Some(parent) => children[*parent].push(built),
+                None => root = Some(built),
+            }
+        }
+        PTree {
+            root: root.expect("The root is always collected"),
+        }
+    }
+
+    pub fn to_tree(&self) -> Tree<IT, LT> {
+        let clone_value = |element: &PElement<IT, LT>| match element.value() {
+            Value::Node(n) => Value::Node(n.clone()),
+            Value::Leaf(l) => Value::Leaf(l.clone()),
+        };
+        let tree = Tree::new(clone_value(&self.root));
+        let mut stack = vec![(tree.root_node(), self.root.clone())];
+        while let Some((element, persistent_element)) = stack.pop() {
+            if let (TreeElement::Node(mut node), PElement::Node(persistent_node)) =
+                (element, persistent_element)
+            {
+                for persistent_child in persistent_node.children() {
+                    let child = node.push_back_child(clone_value(persistent_child));
+                    stack.push((child, persistent_child.clone()));
+                }
+            }
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::persistent::PTree;
+    use crate::test_util::{build_number_tree, describe};
+    use crate::tree::{DLTreeError, Value};
+
+    #[test]
+    fn versions_test() -> Result<(), DLTreeError> {
+        let v1 = PTree::from_tree(&build_number_tree());
+        let v2 = v1.push_child(&[1, 1], Value::Leaf(7))?;
+        let v3 = v2.remove(&[0])?;
+        let v4 = v3.set_value(&[0], Value::Node(8))?;
+        let v5 = v4
+            .insert(&[1], Value::Leaf(9))?
+            .set(&[0, 0], Value::Node(10))?;
+
+        assert_eq!(describe(v1.root()), "1(2,3(4,5()),6)");
+        assert_eq!(describe(v2.root()), "1(2,3(4,5(7)),6)");
+        assert_eq!(describe(v3.root()), "1(3(4,5(7)),6)");
+        assert_eq!(describe(v4.root()), "1(8(4,5(7)),6)");
+        assert_eq!(describe(v5.root()), "1(8(10(),5(7)),9,6)");
+
+        // Unchanged subtrees are shared between the versions
+        assert!(v1.get(&[2]).unwrap().ptr_eq(v2.get(&[2]).unwrap()));
+        assert!(v1.get(&[1, 0]).unwrap().ptr_eq(v3.get(&[0, 0]).unwrap()));
+        assert!(v3.get(&[0, 1]).unwrap().ptr_eq(v4.get(&[0, 1]).unwrap()));
+        assert!(!v1.get(&[1]).unwrap().ptr_eq(v2.get(&[1]).unwrap()));
+        assert!(v1.ptr_eq(&v1.clone()));
+
+        assert_eq!(
+            v1.remove(&[]).unwrap_err(),
+            DLTreeError::ChildOperationOnRootLevel
+        );
+        assert_eq!(v1.get(&[0, 0]).map(|e| e.is_leaf()), None);
+        assert_eq!(
+            v1.set(&[0, 0], Value::Leaf(0)).unwrap_err(),
+            DLTreeError::ElementKindMismatch
+        );
+        assert_eq!(
+            v1.insert(&[5], Value::Leaf(0)).unwrap_err(),
+            DLTreeError::IndexOutOfRange
+        );
+        assert_eq!(
+            v1.set_value(&[0], Value::Node(0)).unwrap_err(),
+            DLTreeError::ElementKindMismatch
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn zipper_test() -> Result<(), DLTreeError> {
+        let v1 = PTree::from_tree(&build_number_tree());
+        let mut zipper = v1.zipper();
+        assert!(!zipper.up());
+        assert!(!zipper.next_sibling());
+        assert!(zipper.down(1));
+        assert!(zipper.next_sibling());
+        assert!(!zipper.next_sibling());
+        assert!(zipper.prev_sibling());
+        assert!(zipper.down(1));
+        assert!(!zipper.down(0));
+        assert_eq!(zipper.path(), vec![1, 1]);
+        assert_eq!(zipper.focus().value(), Value::Node(&5));
+
+        zipper.push_child(Value::Leaf(7))?;
+        assert!(zipper.prev_sibling());
+        zipper.set(Value::Leaf(8));
+        assert!(zipper.up());
+        assert_eq!(describe(zipper.focus()), "3(8,5(7))");
+        zipper.remove_child(0)?;
+        assert_eq!(describe(&zipper.remove()?), "3(5(7))");
+        assert!(zipper.is_root());
+        zipper.insert_child(0, Value::Node(9))?;
+        assert!(zipper.down(0));
+        let v2 = zipper.into_tree();
+
+        assert_eq!(describe(v1.root()), "1(2,3(4,5()),6)");
+        assert_eq!(describe(v2.root()), "1(9(),2,6)");
+        Ok(())
+    }
+
+    #[test]
+    fn conversion_test() {
+        let v1 = PTree::from_tree(&build_number_tree());
+        let tree = v1.to_tree();
+        tree.root_node()
+            .as_node()
+            .unwrap()
+            .push_back_child(Value::Leaf(7));
+        assert_eq!(describe(v1.root()), "1(2,3(4,5()),6)");
+        assert_eq!(
+            describe(PTree::from_tree(&tree).root()),
+            "1(2,3(4,5()),6,7)"
+        );
+
+        let leaf = PTree::<i32, i32>::new(Value::Leaf(1));
+        assert_eq!(*leaf.to_tree().root_node().as_leaf().unwrap().value(), 1);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::internal::node_impl::NodeImpl;
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::search::SearchOrder;
+use crate::tree::{DLTreeError, Tree};
+use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::tree_element_type::Node;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+fn new_node<IT, LT>(value: IT) -> (Node<IT, LT>, TreeElementImpl<IT, LT>) {
+    let node = Rc::new(RefCell::new(NodeImpl::new(value, None)));
+    (Node::new(node.clone()), TreeElementImpl::Node(node))
+}
+
+impl<IT, LT> TreeElement<IT, LT> {
+    // The parent of this element and the index of this element among its children
+    fn position(&self) -> Result<(Node<IT, LT>, usize), DLTreeError> {
+        let parent = self
+            .parent()?
+            .ok_or(DLTreeError::ChildOperationOnRootLevel)?;
+        let index = parent
+            .children()
+            .iter()
+            .position(|child| child.ptr_eq(self))
+            .ok_or(DLTreeError::IntegrityViolated)?;
+        Ok((parent, index))
+    }
+
+    // Replaces this element by a new node with the given value that has this element as its only
+    // child and returns the new node
+    pub fn wrap_in(&self, value: IT) -> Result<Node<IT, LT>, DLTreeError> {
+        let (mut parent, index) = self.position()?;
+        let (mut wrapper, element) = new_node(value);
+        parent.replace_child_at(index, &self.to_impl(), element)?;
+        wrapper.insert_child_at(0, self.to_impl())?;
+        Ok(wrapper)
+    }
+}
+
+impl<IT, LT> Node<IT, LT> {
+    // Moves the children of this node into its parent at the position of this node
+    // and removes this node from the tree
+    pub fn unwrap_into_parent(&mut self) -> Result<(), DLTreeError> {
+        let this = TreeElement::Node(self.clone());
+        let (mut parent, index) = this.position()?;
+        // The children are moved while this node is still part of the tree,
+        // so all changes are reported to the observers of the tree
+        for (offset, child) in self.children().into_iter().enumerate() {
+            let child = child.to_impl();
+            self.remove_child_at(0, &child)?;
+            parent.insert_child_at(index + 1 + offset, child)?;
+        }
+        parent.remove_child_at(index, &this.to_impl())
+    }
+
+    // Moves the children in range under a new node with the given value, which is inserted at the
+    // position of the first moved child, and returns the new node
+    pub fn group_children(
+        &mut self,
+        range: Range<usize>,
+        value: IT,
+    ) -> Result<Node<IT, LT>, DLTreeError> {
+        let children = self.children();
+        if range.start > range.end || range.end > children.len() {
+            return Err(DLTreeError::IndexOutOfRange);
+        }
+        let (mut group, element) = new_node(value);
+        self.insert_child_at(range.start, element)?;
+        for (offset, child) in children[range.clone()].iter().enumerate() {
+            let child = child.to_impl();
+            self.remove_child_at(range.start + 1, &child)?;
+            group.insert_child_at(offset, child)?;
+        }
+        Ok(group)
+    }
+}
+
+impl<IT: Default, LT> Tree<IT, LT> {
+    // Merges every node that has a node as its only child with this child, like the edges of a
+    // radix trie. The value of the merged node is merge(parent value, child value). The values
+    // are moved out of both nodes, the removed child keeps a default value.
+    pub fn compress_chains<F>(&self, mut merge: F) -> Result<(), DLTreeError>
+    where
+        F: FnMut(IT, IT) -> IT,
+    {
+        let mut stack: Vec<Node<IT, LT>> = self.root_node().as_node().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            loop {
+                let children = node.children();
+                let mut child = match children.as_slice() {
+                    [TreeElement::Node(child)] => child.clone(),
+                    _ => break,
+                };
+                {
+                    // Both values are borrowed before either is moved, so that a failed borrow
+                    // leaves the tree unchanged
+                    let mut value = node.try_value_mut()?;
+                    let mut child_value = child.try_value_mut()?;
+                    let merged = merge(
+                        std::mem::take(&mut *value),
+                        std::mem::take(&mut *child_value),
+                    );
+                    *value = merged;
+                }
+                child.unwrap_into_parent()?;
+            }
+            stack.extend(node.children().iter().rev().filter_map(|c| c.as_node()));
+        }
+        Ok(())
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    // The inverse of compress_chains. Every node is replaced by a chain of nodes with the values
+    // returned by split, the children of the node are moved to the last node of the chain.
+    // Nodes for which split returns less than two values are not changed.
+    pub fn expand<F>(&self, mut split: F) -> Result<(), DLTreeError>
+    where
+        F: FnMut(&IT) -> Vec<IT>,
+    {
+        // The nodes are collected first, so that the inserted nodes are not split again
+        let nodes: Vec<Node<IT, LT>> = self
+            .root_node()
+            .traverse(SearchOrder::DepthFirst)
+            .filter_map(|e| e.as_node())
+            .collect();
+        for mut node in nodes {
+            let mut parts = split(&node.value());
+            if parts.len() < 2 {
+                continue;
+            }
+            let last = parts.pop().ok_or(DLTreeError::IntegrityViolated)?;
+            let mut parts = parts.into_iter();
+            let first = parts.next().ok_or(DLTreeError::IntegrityViolated)?;
+            // The chain is built from the bottom up below the node
+            node.group_children(0..node.children().len(), last)?;
+            for part in parts.rev() {
+                node.group_children(0..1, part)?;
+            }
+            *node.value_mut() = first;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{build_tree, describe};
+    use crate::tree::{DLTreeError, Tree};
+
+    #[test]
+    fn chain_test() -> Result<(), DLTreeError> {
+        let tree: Tree<String, u32> = crate::tree!(node "a".to_string() => [
+            node "b".to_string() => [node "c".to_string() => [
+                leaf 1,
+                node "d".to_string() => [node "e".to_string() => [leaf 2]],
+            ]],
+        ]);
+        let original = describe(&tree.root_node());
+        tree.enable_journal(100);
+        // A value that is borrowed elsewhere cannot be merged
+        let root = tree.root_node().as_node().unwrap();
+        let value = root.value();
+        assert_eq!(
+            tree.compress_chains(|parent, child| format!("{}/{}", parent, child)),
+            Err(DLTreeError::ValueBorrowed)
+        );
+        drop(value);
+        assert_eq!(describe(&tree.root_node()), original);
+        tree.compress_chains(|parent, child| format!("{}/{}", parent, child))?;
+        assert_eq!(describe(&tree.root_node()), "a/b/c(1,d/e(2))");
+
+        tree.expand(|value| value.split('/').map(String::from).collect())?;
+        assert_eq!(describe(&tree.root_node()), original);
+        let e = tree.root_node().as_node().unwrap().children()[0]
+            .as_node()
+            .unwrap()
+            .children()[0]
+            .as_node()
+            .unwrap()
+            .children()[1]
+            .as_node()
+            .unwrap()
+            .children()[0]
+            .clone();
+        assert!(e.parent()?.is_some());
+
+        while tree.undo()? {}
+        assert_eq!(describe(&tree.root_node()), original);
+        Ok(())
+    }
+
+    #[test]
+    fn unwrap_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        tree.enable_journal(10);
+        let root = tree.root_node().as_node().unwrap();
+        let mut b = root.children()[1].as_node().unwrap();
+        let two = b.children()[0].clone();
+        b.unwrap_into_parent()?;
+        assert_eq!(describe(&tree.root_node()), "a(1,2,c(3),d(4,e()),5)");
+        assert!(b.children().is_empty());
+        assert!(b.parent()?.is_none());
+        assert!(two.parent()? == Some(root.clone()));
+        assert_eq!(
+            root.clone().unwrap_into_parent(),
+            Err(DLTreeError::ChildOperationOnRootLevel)
+        );
+
+        while tree.undo()? {}
+        assert_eq!(describe(&tree.root_node()), "a(1,b(2,c(3)),d(4,e()),5)");
+        Ok(())
+    }
+
+    #[test]
+    fn wrap_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        tree.enable_journal(10);
+        let root = tree.root_node().as_node().unwrap();
+        let b = root.children()[1].clone();
+        let wrapper = b.wrap_in("w".to_string())?;
+        assert_eq!(describe(&tree.root_node()), "a(1,w(b(2,c(3))),d(4,e()),5)");
+        assert!(b.parent()? == Some(wrapper.clone()));
+        assert!(wrapper.parent()? == Some(root.clone()));
+        root.children()[0].wrap_in("x".to_string())?;
+        assert_eq!(
+            describe(&tree.root_node()),
+            "a(x(1),w(b(2,c(3))),d(4,e()),5)"
+        );
+        assert_eq!(
+            tree.root_node().wrap_in("y".to_string()).unwrap_err(),
+            DLTreeError::ChildOperationOnRootLevel
+        );
+
+        while tree.undo()? {}
+        assert_eq!(describe(&tree.root_node()), "a(1,b(2,c(3)),d(4,e()),5)");
+        Ok(())
+    }
+
+    #[test]
+    fn group_test() -> Result<(), DLTreeError> {
+        let tree = build_tree();
+        tree.enable_journal(10);
+        let mut root = tree.root_node().as_node().unwrap();
+        let group = root.group_children(1..3, "g".to_string())?;
+        assert_eq!(describe(&tree.root_node()), "a(1,g(b(2,c(3)),d(4,e())),5)");
+        assert!(group.children()[1].parent()? == Some(group.clone()));
+        root.group_children(0..0, "f".to_string())?;
+        assert_eq!(
+            describe(&tree.root_node()),
+            "a(f(),1,g(b(2,c(3)),d(4,e())),5)"
+        );
+        assert_eq!(
+            root.group_children(3..5, "h".to_string()).unwrap_err(),
+            DLTreeError::IndexOutOfRange
+        );
+
+        while tree.undo()? {}
+        assert_eq!(describe(&tree.root_node()), "a(1,b(2,c(3)),d(4,e()),5)");
+        Ok(())
+    }
+}
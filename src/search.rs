@@ -0,0 +1,176 @@
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::tree_element_type::{Leaf, Node};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOrder {
+    // Pre-order, children are visited from first to last
+    DepthFirst,
+    // Level by level, children are visited from first to last
+    BreadthFirst,
+}
+
+// Iterates over an element and all of its descendants.
+// The children of an element are only looked up when the element is reached,
+// so stopping the iteration early does not visit the remaining elements.
+pub struct Traverse<IT, LT> {
+    order: SearchOrder,
+    pending: VecDeque<TreeElementImpl<IT, LT>>,
+}
+
+impl<IT, LT> Iterator for Traverse<IT, LT> {
+    type Item = TreeElement<IT, LT>;
+
+    fn next(&mut self) -> Option<TreeElement<IT, LT>> {
+        let element = match self.order {
+            SearchOrder::DepthFirst => self.pending.pop_back()?,
+            SearchOrder::BreadthFirst => self.pending.pop_front()?,
+        };
+        if let TreeElementImpl::Node(n) = &element {
+            let node = n.borrow();
+            match self.order {
+                SearchOrder::DepthFirst => self.pending.extend(node.children.iter().rev().cloned()),
+                SearchOrder::BreadthFirst => self.pending.extend(node.children.iter().cloned()),
+            }
+        }
+        Some(TreeElement::new(&element))
+    }
+}
+
+impl<IT, LT> TreeElement<IT, LT> {
+    // The element itself is the first element of the traversal
+    pub fn traverse(&self, order: SearchOrder) -> Traverse<IT, LT> {
+        Traverse {
+            order,
+            pending: VecDeque::from(vec![self.to_impl()]),
+        }
+    }
+}
+
+// All searches include the node itself
+impl<IT, LT> Node<IT, LT> {
+    pub fn traverse(&self, order: SearchOrder) -> Traverse<IT, LT> {
+        TreeElement::Node(self.clone()).traverse(order)
+    }
+
+    pub fn find<P>(&self, order: SearchOrder, mut predicate: P) -> Option<TreeElement<IT, LT>>
+    where
+        P: FnMut(&TreeElement<IT, LT>) -> bool,
+    {
+        self.traverse(order).find(|element| predicate(element))
+    }
+
+    pub fn find_all<P>(&self, order: SearchOrder, mut predicate: P) -> Vec<TreeElement<IT, LT>>
+    where
+        P: FnMut(&TreeElement<IT, LT>) -> bool,
+    {
+        self.traverse(order)
+            .filter(|element| predicate(element))
+            .collect()
+    }
+
+    pub fn find_node<P>(&self, order: SearchOrder, mut predicate: P) -> Option<Node<IT, LT>>
+    where
+        P: FnMut(&IT) -> bool,
+    {
+        self.traverse(order)
+            .filter_map(|element| element.as_node())
+            .find(|node| predicate(&node.value()))
+    }
+
+    pub fn find_leaf<P>(&self, order: SearchOrder, mut predicate: P) -> Option<Leaf<IT, LT>>
+    where
+        P: FnMut(&LT) -> bool,
+    {
+        self.traverse(order)
+            .filter_map(|element| element.as_leaf())
+            .find(|leaf| predicate(&leaf.value()))
+    }
+
+    pub fn find_nodes<P>(&self, order: SearchOrder, mut predicate: P) -> Vec<Node<IT, LT>>
+    where
+        P: FnMut(&IT) -> bool,
+    {
+        self.traverse(order)
+            .filter_map(|element| element.as_node())
+            .filter(|node| predicate(&node.value()))
+            .collect()
+    }
+
+    pub fn find_leaves<P>(&self, order: SearchOrder, mut predicate: P) -> Vec<Leaf<IT, LT>>
+    where
+        P: FnMut(&LT) -> bool,
+    {
+        self.traverse(order)
+            .filter_map(|element| element.as_leaf())
+            .filter(|leaf| predicate(&leaf.value()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::SearchOrder;
+    use crate::test_util::{build_tree, label};
+
+    #[test]
+    fn order_test() {
+        let tree = build_tree();
+        let root = tree.root_node().as_node().unwrap();
+        let all = |order| {
+            root.find_all(order, |_| true)
+                .iter()
+                .map(label)
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        assert_eq!(all(SearchOrder::DepthFirst), "a,1,b,2,c,3,d,4,e,5");
+        assert_eq!(all(SearchOrder::BreadthFirst), "a,1,b,d,5,2,c,4,e,3");
+    }
+
+    #[test]
+    fn find_test() {
+        let tree = build_tree();
+        let root = tree.root_node().as_node().unwrap();
+        let b = root
+            .find_node(SearchOrder::DepthFirst, |v| v == "b")
+            .unwrap();
+        assert_eq!(
+            b.find_leaf(SearchOrder::BreadthFirst, |v| *v > 1)
+                .map(|l| *l.value()),
+            Some(2)
+        );
+        assert!(b.find_leaf(SearchOrder::DepthFirst, |v| *v == 4).is_none());
+
+        // The first leaf greater than 2 depends on the order
+        let first = |order| *root.find_leaf(order, |v| *v > 2).unwrap().value();
+        assert_eq!(first(SearchOrder::DepthFirst), 3);
+        assert_eq!(first(SearchOrder::BreadthFirst), 5);
+
+        let nodes = root.find_nodes(SearchOrder::BreadthFirst, |v| v != "b");
+        assert_eq!(
+            nodes.iter().map(|n| n.value().clone()).collect::<Vec<_>>(),
+            vec!["a", "d", "c", "e"]
+        );
+        let leaves = root.find_leaves(SearchOrder::DepthFirst, |v| v % 2 == 1);
+        assert_eq!(
+            leaves.iter().map(|l| *l.value()).collect::<Vec<_>>(),
+            vec![1, 3, 5]
+        );
+        let found = root.find(SearchOrder::DepthFirst, |e| e.as_leaf().is_some());
+        assert_eq!(label(&found.unwrap()), "1");
+    }
+
+    #[test]
+    fn early_termination_test() {
+        let tree = build_tree();
+        let root = tree.root_node().as_node().unwrap();
+        let mut visited = 0;
+        root.find(SearchOrder::DepthFirst, |e| {
+            visited += 1;
+            label(e) == "2"
+        });
+        assert_eq!(visited, 4);
+    }
+}
@@ -0,0 +1,475 @@
+use crate::search::SearchOrder;
+use crate::tree::{Tree, Value};
+use crate::tree_elements::tree_element::TreeElement;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+// Selectors address elements with path expressions like `root/section[2]/*/leaf[name="x"]`.
+//
+// The expression is a sequence of steps separated by `/` (children) or `//` (descendants).
+// The first step is matched against the root itself, `//` at the start matches any element.
+// A step consists of a test and any number of predicates in brackets:
+// - `*` matches all elements, `node` only nodes and `leaf` only leaves
+// - any other identifier is a name test and is equivalent to `*[name="identifier"]`
+// - `[2]` selects the element at position 2 (counting from 0) among the elements matched so far
+// - `[key="argument"]` and `[key]` call the value predicate registered as key
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectorError {
+    // The expression ended where a step or a predicate was expected
+    UnexpectedEnd,
+    // The character at the given byte position is not valid at this place
+    UnexpectedCharacter(usize),
+    // A value predicate is used that has not been registered
+    UnknownPredicate(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Child,
+    Descendant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Test {
+    Any,
+    Node,
+    Leaf,
+    Name(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Predicate {
+    Index(usize),
+    Value {
+        key: String,
+        argument: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    axis: Axis,
+    test: Test,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    steps: Vec<Step>,
+}
+
+type ValuePredicate<IT, LT> = Box<dyn Fn(Value<&IT, &LT>, Option<&str>) -> bool>;
+
+// Value predicates that can be used by selectors, keyed by name.
+// The predicate registered as "name" is used for name tests.
+pub struct SelectorPredicates<IT, LT> {
+    predicates: HashMap<String, ValuePredicate<IT, LT>>,
+}
+
+impl<IT, LT> SelectorPredicates<IT, LT> {
+    pub fn new() -> Self {
+        SelectorPredicates {
+            predicates: HashMap::new(),
+        }
+    }
+
+    // The predicate receives the value of the tested element and the argument of the
+    // predicate, which is None for predicates without argument
+    pub fn register<F>(mut self, key: &str, predicate: F) -> Self
+    where
+        F: Fn(Value<&IT, &LT>, Option<&str>) -> bool + 'static,
+    {
+        self.predicates.insert(key.to_string(), Box::new(predicate));
+        self
+    }
+
+    fn test(
+        &self,
+        key: &str,
+        element: &TreeElement<IT, LT>,
+        argument: Option<&str>,
+    ) -> Result<bool, SelectorError> {
+        let predicate = self
+            .predicates
+            .get(key)
+            .ok_or_else(|| SelectorError::UnknownPredicate(key.to_string()))?;
+        Ok(match element {
+            TreeElement::Node(n) => predicate(Value::Node(&n.value()), argument),
+            TreeElement::Leaf(l) => predicate(Value::Leaf(&l.value()), argument),
+        })
+    }
+}
+
+impl<IT, LT> Default for SelectorPredicates<IT, LT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Parser<'a> {
+    expression: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.expression[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        if self.expression[self.position..].starts_with(expected) {
+            self.position += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SelectorError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += c.len_utf8();
+                Ok(())
+            }
+            Some(_) => Err(SelectorError::UnexpectedCharacter(self.position)),
+            None => Err(SelectorError::UnexpectedEnd),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, accept: F) -> &str {
+        let start = self.position;
+        while let Some(c) = self.peek().filter(|c| accept(*c)) {
+            self.position += c.len_utf8();
+        }
+        &self.expression[start..self.position]
+    }
+
+    fn identifier(&mut self) -> Result<String, SelectorError> {
+        match self.peek() {
+            None => return Err(SelectorError::UnexpectedEnd),
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            Some(_) => return Err(SelectorError::UnexpectedCharacter(self.position)),
+        }
+        Ok(self
+            .take_while(|c| c.is_alphanumeric() || c == '_' || c == '-')
+            .to_string())
+    }
+
+    // A double quoted string, quotes and backslashes inside are escaped with a backslash
+    fn string(&mut self) -> Result<String, SelectorError> {
+        self.expect('"')?;
+        let mut result = String::new();
+        loop {
+            let c = self.peek().ok_or(SelectorError::UnexpectedEnd)?;
+            self.position += c.len_utf8();
+            match c {
+                '"' => return Ok(result),
+                '\\' => {
+                    let escaped = self.peek().ok_or(SelectorError::UnexpectedEnd)?;
+                    self.position += escaped.len_utf8();
+                    result.push(escaped);
+                }
+                c => result.push(c),
+            }
+        }
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, SelectorError> {
+        self.skip_whitespace();
+        let predicate = if self.peek().map_or(false, |c| c.is_ascii_digit()) {
+            let start = self.position;
+            let digits = self.take_while(|c| c.is_ascii_digit());
+            Predicate::Index(
+                digits
+                    .parse()
+                    .map_err(|_| SelectorError::UnexpectedCharacter(start))?,
+            )
+        } else {
+            let key = self.identifier()?;
+            self.skip_whitespace();
+            let argument = if self.eat("=") {
+                self.skip_whitespace();
+                Some(self.string()?)
+            } else {
+                None
+            };
+            Predicate::Value { key, argument }
+        };
+        self.skip_whitespace();
+        self.expect(']')?;
+        Ok(predicate)
+    }
+
+    fn step(&mut self, axis: Axis) -> Result<Step, SelectorError> {
+        let test = if self.eat("*") {
+            Test::Any
+        } else {
+            match self.identifier()?.as_str() {
+                "node" => Test::Node,
+                "leaf" => Test::Leaf,
+                name => Test::Name(name.to_string()),
+            }
+        };
+        let mut predicates = vec![];
+        while self.eat("[") {
+            predicates.push(self.predicate()?);
+        }
+        Ok(Step {
+            axis,
+            test,
+            predicates,
+        })
+    }
+
+    fn axis(&mut self) -> Option<Axis> {
+        if self.eat("//") {
+            Some(Axis::Descendant)
+        } else if self.eat("/") {
+            Some(Axis::Child)
+        } else {
+            None
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, SelectorError> {
+        let mut steps = vec![];
+        let mut axis = self.axis().unwrap_or(Axis::Child);
+        loop {
+            steps.push(self.step(axis)?);
+            if self.peek().is_none() {
+                return Ok(Selector { steps });
+            }
+            axis = self
+                .axis()
+                .ok_or(SelectorError::UnexpectedCharacter(self.position))?;
+        }
+    }
+}
+
+fn element_key<IT, LT>(element: &TreeElement<IT, LT>) -> *const () {
+    match element {
+        TreeElement::Node(n) => Rc::as_ptr(&n.element_impl) as *const (),
+        TreeElement::Leaf(l) => Rc::as_ptr(&l.element_impl) as *const (),
+    }
+}
+
+impl Step {
+    fn matches<IT, LT>(
+        &self,
+        element: &TreeElement<IT, LT>,
+        predicates: &SelectorPredicates<IT, LT>,
+    ) -> Result<bool, SelectorError> {
+        match &self.test {
+            Test::Any => Ok(true),
+            Test::Node => Ok(element.as_node().is_some()),
+            Test::Leaf => Ok(element.as_leaf().is_some()),
+            Test::Name(name) => predicates.test("name", element, Some(name)),
+        }
+    }
+
+    // Applies the test and the predicates to the candidates of a single context element
+    fn filter<IT, LT>(
+        &self,
+        candidates: Vec<TreeElement<IT, LT>>,
+        predicates: &SelectorPredicates<IT, LT>,
+    ) -> Result<Vec<TreeElement<IT, LT>>, SelectorError> {
+        let mut matched = vec![];
+        for candidate in candidates {
+            if self.matches(&candidate, predicates)? {
+                matched.push(candidate);
+            }
+        }
+        for predicate in &self.predicates {
+            matched = match predicate {
+                Predicate::Index(index) => matched.into_iter().skip(*index).take(1).collect(),
+                Predicate::Value { key, argument } => {
+                    let mut filtered = vec![];
+                    for element in matched {
+                        if predicates.test(key, &element, argument.as_deref())? {
+                            filtered.push(element);
+                        }
+                    }
+                    filtered
+                }
+            };
+        }
+        Ok(matched)
+    }
+}
+
+impl Selector {
+    pub fn parse(expression: &str) -> Result<Selector, SelectorError> {
+        Parser {
+            expression,
+            position: 0,
+        }
+        .selector()
+    }
+
+    // Returns the matched elements in the order they are found, each element only once
+    pub fn select<IT, LT>(
+        &self,
+        tree: &Tree<IT, LT>,
+        predicates: &SelectorPredicates<IT, LT>,
+    ) -> Result<Vec<TreeElement<IT, LT>>, SelectorError> {
+        let root = tree.root_node();
+        // The root is the only child of a virtual element that is the initial context
+        let mut context: Vec<Option<TreeElement<IT, LT>>> = vec![None];
+        for step in &self.steps {
+            let mut selected = vec![];
+            let mut seen = HashSet::new();
+            for element in context {
+                let candidates = match (element, step.axis) {
+                    (None, Axis::Child) => vec![root.clone()],
+                    (None, Axis::Descendant) => root.traverse(SearchOrder::DepthFirst).collect(),
+                    (Some(TreeElement::Node(n)), Axis::Child) => n.children(),
+                    (Some(TreeElement::Node(n)), Axis::Descendant) => {
+                        n.traverse(SearchOrder::DepthFirst).skip(1).collect()
+                    }
+                    (Some(TreeElement::Leaf(_)), _) => vec![],
+                };
+                for matched in step.filter(candidates, predicates)? {
+                    if seen.insert(element_key(&matched)) {
+                        selected.push(matched);
+                    }
+                }
+            }
+            context = selected.into_iter().map(Some).collect();
+        }
+        Ok(context.into_iter().flatten().collect())
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    pub fn select(
+        &self,
+        expression: &str,
+        predicates: &SelectorPredicates<IT, LT>,
+    ) -> Result<Vec<TreeElement<IT, LT>>, SelectorError> {
+        Selector::parse(expression)?.select(self, predicates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::selector::{Selector, SelectorError, SelectorPredicates};
+    use crate::tree::{Tree, Value};
+    use crate::tree_elements::tree_element::TreeElement;
+
+    type Config = Tree<String, (String, u32)>;
+
+    fn build_config() -> Config {
+        let node = |name: &str| name.to_string();
+        let leaf = |name: &str, value: u32| (name.to_string(), value);
+        crate::tree!(node node("root") => [
+            node node("section") => [
+                node node("group") => [leaf leaf("x", 1), leaf leaf("y", 2)],
+            ],
+            node node("section") => [
+                node node("group") => [leaf leaf("x", 3), leaf leaf("x", 4), leaf leaf("z", 5)],
+                node node("group") => [leaf leaf("x", 6)],
+                leaf leaf("x", 7),
+            ],
+            node node("other"),
+        ])
+    }
+
+    fn predicates() -> SelectorPredicates<String, (String, u32)> {
+        SelectorPredicates::<String, (String, u32)>::new()
+            .register("name", |value, argument| match value {
+                Value::Node(name) => Some(name.as_str()) == argument,
+                Value::Leaf((name, _)) => Some(name.as_str()) == argument,
+            })
+            .register("even", |value, _| match value {
+                Value::Node(_) => false,
+                Value::Leaf((_, value)) => value % 2 == 0,
+            })
+    }
+
+    fn select(tree: &Config, expression: &str) -> Result<Vec<String>, SelectorError> {
+        Ok(tree
+            .select(expression, &predicates())?
+            .iter()
+            .map(|element| match element {
+                TreeElement::Node(n) => n.value().clone(),
+                TreeElement::Leaf(l) => format!("{}={}", l.value().0, l.value().1),
+            })
+            .collect())
+    }
+
+    #[test]
+    fn selection_test() -> Result<(), SelectorError> {
+        let tree = build_config();
+        assert_eq!(
+            select(&tree, "root/section[1]/*/leaf[name=\"x\"]")?,
+            vec!["x=3", "x=4", "x=6"]
+        );
+        assert_eq!(select(&tree, "/root")?, vec!["root"]);
+        assert_eq!(select(&tree, "section")?, Vec::<String>::new());
+        assert_eq!(
+            select(&tree, "*/node")?,
+            vec!["section", "section", "other"]
+        );
+        assert_eq!(select(&tree, "//leaf[even]")?, vec!["y=2", "x=4", "x=6"]);
+        assert_eq!(select(&tree, "//x[1]")?, vec!["x=3"]);
+        assert_eq!(
+            select(&tree, "root//group/x[0]")?,
+            vec!["x=1", "x=3", "x=6"]
+        );
+        assert_eq!(select(&tree, "root/section//leaf[ even ][1]")?, vec!["x=6"]);
+        // Overlapping descendant steps select every element only once
+        assert_eq!(select(&tree, "//node//x")?.len(), 5);
+        assert_eq!(
+            select(&tree, "//*[name=\"other\"]/*")?,
+            Vec::<String>::new()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn error_test() {
+        let tree = build_config();
+        assert_eq!(Selector::parse("root/"), Err(SelectorError::UnexpectedEnd));
+        assert_eq!(
+            Selector::parse("root/section[1"),
+            Err(SelectorError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Selector::parse("root/[1]"),
+            Err(SelectorError::UnexpectedCharacter(5))
+        );
+        assert_eq!(
+            Selector::parse("root/*[name=x]"),
+            Err(SelectorError::UnexpectedCharacter(12))
+        );
+        assert_eq!(
+            Selector::parse("root section"),
+            Err(SelectorError::UnexpectedCharacter(4))
+        );
+        // Whitespace that is longer than a single byte
+        assert!(Selector::parse("root/*[\u{00A0}name]").is_ok());
+        assert_eq!(
+            Selector::parse("root/*[\u{00A0}\u{00A0}]"),
+            Err(SelectorError::UnexpectedCharacter(11))
+        );
+        assert_eq!(
+            select(&tree, "root/*[odd]"),
+            Err(SelectorError::UnknownPredicate("odd".to_string()))
+        );
+        assert_eq!(
+            tree.select("root", &SelectorPredicates::new()).unwrap_err(),
+            SelectorError::UnknownPredicate("name".to_string())
+        );
+        assert_eq!(
+            Selector::parse("*[name=\"a\\\"b\"]"),
+            Selector::parse("*[ name = \"a\\\"b\" ]")
+        );
+    }
+}
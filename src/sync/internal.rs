@@ -0,0 +1,105 @@
+use crate::tree::Value;
+use std::collections::VecDeque;
+use std::sync::{
+    Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak,
+};
+
+pub type ParentSlot<IT, LT> = Mutex<Option<Weak<SyncNodeImpl<IT, LT>>>>;
+
+pub trait SyncTreeElementTrait<IT, LT> {
+    fn parent(&self) -> &ParentSlot<IT, LT>;
+}
+
+#[derive(Debug)]
+pub struct SyncNodeImpl<IT, LT> {
+    pub parent: ParentSlot<IT, LT>,
+    pub value: RwLock<IT>,
+    pub children: Mutex<VecDeque<SyncTreeElementImpl<IT, LT>>>,
+}
+
+impl<IT, LT> SyncNodeImpl<IT, LT> {
+    pub fn new(value: IT, parent: Option<Weak<SyncNodeImpl<IT, LT>>>) -> Self {
+        SyncNodeImpl {
+            parent: Mutex::new(parent),
+            value: RwLock::new(value),
+            children: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl<IT, LT> SyncTreeElementTrait<IT, LT> for SyncNodeImpl<IT, LT> {
+    fn parent(&self) -> &ParentSlot<IT, LT> {
+        &self.parent
+    }
+}
+
+#[derive(Debug)]
+pub struct SyncLeafImpl<IT, LT> {
+    pub parent: ParentSlot<IT, LT>,
+    pub value: RwLock<LT>,
+}
+
+impl<IT, LT> SyncLeafImpl<IT, LT> {
+    pub fn new(value: LT, parent: Option<Weak<SyncNodeImpl<IT, LT>>>) -> Self {
+        SyncLeafImpl {
+            parent: Mutex::new(parent),
+            value: RwLock::new(value),
+        }
+    }
+}
+
+impl<IT, LT> SyncTreeElementTrait<IT, LT> for SyncLeafImpl<IT, LT> {
+    fn parent(&self) -> &ParentSlot<IT, LT> {
+        &self.parent
+    }
+}
+
+#[derive(Debug)]
+pub enum SyncTreeElementImpl<IT, LT> {
+    Node(Arc<SyncNodeImpl<IT, LT>>),
+    Leaf(Arc<SyncLeafImpl<IT, LT>>),
+}
+
+impl<IT, LT> SyncTreeElementImpl<IT, LT> {
+    pub fn new(value: Value<IT, LT>, parent: Option<Weak<SyncNodeImpl<IT, LT>>>) -> Self {
+        match value {
+            Value::Node(n) => Self::Node(Arc::new(SyncNodeImpl::new(n, parent))),
+            Value::Leaf(l) => Self::Leaf(Arc::new(SyncLeafImpl::new(l, parent))),
+        }
+    }
+    pub fn parent(&self) -> &ParentSlot<IT, LT> {
+        match self {
+            SyncTreeElementImpl::Node(n) => &n.parent,
+            SyncTreeElementImpl::Leaf(l) => &l.parent,
+        }
+    }
+    pub fn as_ptr(&self) -> *const () {
+        match self {
+            SyncTreeElementImpl::Node(n) => Arc::as_ptr(n) as *const (),
+            SyncTreeElementImpl::Leaf(l) => Arc::as_ptr(l) as *const (),
+        }
+    }
+}
+
+impl<IT, LT> Clone for SyncTreeElementImpl<IT, LT> {
+    fn clone(&self) -> Self {
+        match &self {
+            SyncTreeElementImpl::Node(n) => SyncTreeElementImpl::Node(n.clone()),
+            SyncTreeElementImpl::Leaf(l) => SyncTreeElementImpl::Leaf(l.clone()),
+        }
+    }
+}
+
+// A poisoned lock only means that another thread panicked while holding it.
+// All structural updates are completed before user code is called, so the data is still consistent.
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
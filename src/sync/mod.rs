@@ -0,0 +1,126 @@
+// Thread-safe variant of Tree, Node and Leaf backed by Arc, Mutex and RwLock.
+//
+// Lock ordering rules that keep all operations deadlock free:
+//  1. Every element has three independent locks: its parent pointer, its children and its value.
+//  2. The only lock that is ever held while acquiring another lock are the children of a node.
+//     While holding them, only the parent pointers of its (new or former) children are locked.
+//     Locks are therefore always acquired from top to bottom and never from a child to its parent.
+//  3. Values are never locked while holding a structural lock. Sorting and deep cloning work on
+//     snapshots of the children, so user code (comparators) never runs while a lock is held.
+//  4. Child operations like set or remove_from_tree read the parent pointer first and lock the
+//     children of the parent afterwards. If the element has been moved in between, it is looked up
+//     again in its new parent.
+use crate::sync::internal::SyncTreeElementImpl;
+use crate::sync::sync_tree_element::SyncTreeElement;
+use crate::tree::Value;
+
+mod internal;
+pub mod sync_tree_element;
+pub mod sync_tree_element_type;
+
+pub struct SyncTree<IT, LT> {
+    pub(crate) tree: SyncTreeElementImpl<IT, LT>,
+}
+
+impl<IT, LT> SyncTree<IT, LT> {
+    pub fn new(root_value: Value<IT, LT>) -> Self {
+        SyncTree {
+            tree: SyncTreeElementImpl::new(root_value, None),
+        }
+    }
+    pub fn root_node(&self) -> SyncTreeElement<IT, LT> {
+        SyncTreeElement::new(&self.tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sync::sync_tree_element::SyncTreeElement;
+    use crate::sync::SyncTree;
+    use crate::test_util::describe;
+    use crate::tree::{DLTreeError, Value};
+    use crate::DeepClone;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[test]
+    fn sync_tree_building_test() -> Result<(), DLTreeError> {
+        let tree = SyncTree::new(Value::Node(1));
+        assert_send_sync(&tree);
+        let mut root = tree.root_node().as_node().unwrap();
+        assert_send_sync(&root);
+        let mut leaf = root.push_back_child(Value::Leaf(2));
+        let mut node = root.push_back_child(Value::Node(3)).as_node().unwrap();
+        node.push_front_child(Value::Leaf(4));
+        leaf.as_leaf().unwrap().insert_after(Value::Leaf(5))?;
+        assert_eq!(describe(&tree.root_node()), "1(2,5,3(4))");
+
+        let replaced = leaf.set_node(6)?;
+        assert_eq!(replaced.parent()?.unwrap(), root);
+        assert!(leaf.parent()?.is_none());
+        let removed = node.remove_from_tree()?;
+        assert!(removed.root_node().parent()?.is_none());
+        assert_eq!(describe(&tree.root_node()), "1(6(),5)");
+
+        root.sort_children_unstable(|a, b| a.value().cmp(&b.value()));
+        assert_eq!(describe(&tree.root_node()), "1(5,6())");
+        root.push_child_tree(removed.root_node());
+        let cloned = root.deep_clone();
+        *root.value_mut() = 7;
+        assert_eq!(describe(&SyncTreeElement::Node(cloned)), "1(5,6(),3(4))");
+        root.remove_all_children()?;
+        assert_eq!(describe(&tree.root_node()), "7()");
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_modification_test() -> Result<(), DLTreeError> {
+        let tree = SyncTree::new(Value::Node(0));
+        let root = tree.root_node().as_node().unwrap();
+        let mut left = root
+            .clone()
+            .push_back_child(Value::Node(1))
+            .as_node()
+            .unwrap();
+        let right = root
+            .clone()
+            .push_back_child(Value::Node(2))
+            .as_node()
+            .unwrap();
+        let leaves: Vec<_> = (0..100)
+            .map(|i| left.push_back_child(Value::Leaf(i)))
+            .collect();
+
+        let workers: Vec<_> = leaves
+            .chunks(25)
+            .map(|chunk| {
+                let chunk = chunk.to_vec();
+                let mut right = right.clone();
+                let left = left.clone();
+                thread::spawn(move || {
+                    for mut leaf in chunk {
+                        let removed = leaf.remove_from_tree().unwrap();
+                        right.push_child_tree(removed.root_node());
+                        left.children();
+                        *leaf.as_leaf().unwrap().value_mut() += 1000;
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert!(left.children().is_empty());
+        let mut right = right;
+        right.sort_children_unstable(|a, b| a.value().cmp(&b.value()));
+        let values: Vec<i32> = right.children().iter().map(|c| *c.value()).collect();
+        assert_eq!(values, (1000..1100).collect::<Vec<i32>>());
+        assert!(right
+            .children()
+            .iter()
+            .all(|c| c.parent().unwrap().unwrap() == right));
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use crate::sync::internal::SyncTreeElementImpl;
+use crate::sync::sync_tree_element_type::{SyncLeaf, SyncNode};
+use crate::sync::SyncTree;
+use crate::tree::{DLTreeError, Value};
+use crate::DeepClone;
+use std::sync::{RwLockReadGuard, RwLockWriteGuard};
+
+#[derive(Debug)]
+pub enum SyncTreeElement<IT, LT> {
+    Node(SyncNode<IT, LT>),
+    Leaf(SyncLeaf<IT, LT>),
+}
+
+impl<IT, LT> SyncTreeElement<IT, LT> {
+    pub fn new(value: &SyncTreeElementImpl<IT, LT>) -> Self {
+        match value {
+            SyncTreeElementImpl::Node(n) => SyncTreeElement::Node(SyncNode::new(n.clone())),
+            SyncTreeElementImpl::Leaf(l) => SyncTreeElement::Leaf(SyncLeaf::new(l.clone())),
+        }
+    }
+    pub(crate) fn into_impl(self) -> SyncTreeElementImpl<IT, LT> {
+        match self {
+            SyncTreeElement::Node(n) => SyncTreeElementImpl::Node(n.element_impl),
+            SyncTreeElement::Leaf(l) => SyncTreeElementImpl::Leaf(l.element_impl),
+        }
+    }
+    pub fn as_node(&self) -> Option<SyncNode<IT, LT>> {
+        match self {
+            SyncTreeElement::Node(n) => Some(n.clone()),
+            SyncTreeElement::Leaf(_) => None,
+        }
+    }
+    pub fn as_leaf(&self) -> Option<SyncLeaf<IT, LT>> {
+        match self {
+            SyncTreeElement::Node(_) => None,
+            SyncTreeElement::Leaf(l) => Some(l.clone()),
+        }
+    }
+    pub fn parent(&self) -> Result<Option<SyncNode<IT, LT>>, DLTreeError> {
+        match self {
+            SyncTreeElement::Node(n) => n.parent(),
+            SyncTreeElement::Leaf(l) => l.parent(),
+        }
+    }
+    pub fn remove_from_tree(&mut self) -> Result<SyncTree<IT, LT>, DLTreeError> {
+        match self {
+            SyncTreeElement::Node(n) => n.remove_from_tree(),
+            SyncTreeElement::Leaf(l) => l.remove_from_tree(),
+        }
+    }
+    pub fn set(&mut self, value: Value<IT, LT>) -> Result<SyncTreeElement<IT, LT>, DLTreeError> {
+        match self {
+            SyncTreeElement::Node(n) => n.set(value),
+            SyncTreeElement::Leaf(l) => l.set(value),
+        }
+    }
+    pub fn set_leaf(&mut self, value: LT) -> Result<SyncLeaf<IT, LT>, DLTreeError> {
+        match self {
+            SyncTreeElement::Node(n) => n.set_leaf(value),
+            SyncTreeElement::Leaf(l) => l.set_leaf(value),
+        }
+    }
+    pub fn set_node(&mut self, value: IT) -> Result<SyncNode<IT, LT>, DLTreeError> {
+        match self {
+            SyncTreeElement::Node(n) => n.set_node(value),
+            SyncTreeElement::Leaf(l) => l.set_node(value),
+        }
+    }
+}
+
+impl<IT, LT> Clone for SyncTreeElement<IT, LT> {
+    fn clone(&self) -> Self {
+        match &self {
+            SyncTreeElement::Node(n) => SyncTreeElement::Node(n.clone()),
+            SyncTreeElement::Leaf(l) => SyncTreeElement::Leaf(l.clone()),
+        }
+    }
+}
+
+impl<IT: Clone, LT: Clone> DeepClone for SyncTreeElement<IT, LT> {
+    fn deep_clone(&self) -> Self {
+        match self {
+            SyncTreeElement::Node(n) => SyncTreeElement::Node(n.deep_clone()),
+            SyncTreeElement::Leaf(l) => SyncTreeElement::Leaf(l.deep_clone()),
+        }
+    }
+}
+
+impl<T> SyncTreeElement<T, T> {
+    pub fn value(&self) -> RwLockReadGuard<'_, T> {
+        match self {
+            SyncTreeElement::Node(n) => n.value(),
+            SyncTreeElement::Leaf(l) => l.value(),
+        }
+    }
+    pub fn value_mut(&mut self) -> RwLockWriteGuard<'_, T> {
+        match self {
+            SyncTreeElement::Node(n) => n.value_mut(),
+            SyncTreeElement::Leaf(l) => l.value_mut(),
+        }
+    }
+}
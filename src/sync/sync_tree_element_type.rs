@@ -0,0 +1,272 @@
+use crate::sync::internal::{
+    lock, read, write, SyncLeafImpl, SyncNodeImpl, SyncTreeElementImpl, SyncTreeElementTrait,
+};
+use crate::sync::sync_tree_element::SyncTreeElement;
+use crate::sync::SyncTree;
+use crate::tree::{DLTreeError, Value};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::{Arc, RwLockReadGuard, RwLockWriteGuard, Weak};
+
+#[derive(Debug)]
+pub struct SyncTreeElementType<IT, LT, T: SyncTreeElementTrait<IT, LT>> {
+    pub(crate) element_impl: Arc<T>,
+    phantom_it: PhantomData<IT>,
+    phantom_lt: PhantomData<LT>,
+}
+
+pub type SyncNode<IT, LT> = SyncTreeElementType<IT, LT, SyncNodeImpl<IT, LT>>;
+pub type SyncLeaf<IT, LT> = SyncTreeElementType<IT, LT, SyncLeafImpl<IT, LT>>;
+
+impl<IT, LT, T: SyncTreeElementTrait<IT, LT>> SyncTreeElementType<IT, LT, T> {
+    pub fn new(value: Arc<T>) -> Self {
+        SyncTreeElementType {
+            element_impl: value,
+            phantom_it: PhantomData,
+            phantom_lt: PhantomData,
+        }
+    }
+
+    fn as_ptr(&self) -> *const () {
+        Arc::as_ptr(&self.element_impl) as *const ()
+    }
+
+    // Locks the children of the parent and calls update_fn with the index of this element.
+    // The parent pointer is read before the children of the parent are locked, so a concurrent
+    // move of this element is detected by not finding it among the children and retried.
+    fn update_as_child<F, R>(&self, update_fn: F) -> Result<R, DLTreeError>
+    where
+        F: FnOnce(
+            usize,
+            &mut VecDeque<SyncTreeElementImpl<IT, LT>>,
+            Weak<SyncNodeImpl<IT, LT>>,
+        ) -> Result<R, DLTreeError>,
+    {
+        loop {
+            let parent = self
+                .parent()?
+                .ok_or(DLTreeError::ChildOperationOnRootLevel)?
+                .element_impl;
+            let mut children = lock(&parent.children);
+            if let Some(index) = children.iter().position(|c| c.as_ptr() == self.as_ptr()) {
+                return update_fn(index, &mut children, Arc::downgrade(&parent));
+            }
+            drop(children);
+            let moved = match &*lock(self.element_impl.parent()) {
+                Some(current_parent) => Weak::as_ptr(current_parent) != Arc::as_ptr(&parent),
+                None => true,
+            };
+            if !moved {
+                return Err(DLTreeError::IntegrityViolated);
+            }
+        }
+    }
+
+    pub fn parent(&self) -> Result<Option<SyncNode<IT, LT>>, DLTreeError> {
+        match &*lock(self.element_impl.parent()) {
+            None => Ok(None),
+            Some(p) => match p.upgrade() {
+                // See TreeElementType::parent
+                None => Err(DLTreeError::IntegrityViolated),
+                Some(upgraded_p) => Ok(Some(SyncTreeElementType::new(upgraded_p))),
+            },
+        }
+    }
+
+    fn replace(
+        &mut self,
+        value: Value<IT, LT>,
+    ) -> Result<SyncTreeElementImpl<IT, LT>, DLTreeError> {
+        self.update_as_child(|index, children, parent| {
+            let child = children
+                .get_mut(index)
+                .ok_or(DLTreeError::IntegrityViolated)?;
+            let new_child = SyncTreeElementImpl::new(value, Some(parent));
+            let old_child = std::mem::replace(child, new_child.clone());
+            *lock(old_child.parent()) = None;
+            Ok(new_child)
+        })
+    }
+
+    pub fn set(&mut self, value: Value<IT, LT>) -> Result<SyncTreeElement<IT, LT>, DLTreeError> {
+        Ok(SyncTreeElement::new(&self.replace(value)?))
+    }
+
+    pub fn set_leaf(&mut self, value: LT) -> Result<SyncLeaf<IT, LT>, DLTreeError> {
+        match self.replace(Value::Leaf(value))? {
+            SyncTreeElementImpl::Leaf(l) => Ok(SyncLeaf::new(l)),
+            SyncTreeElementImpl::Node(_) => Err(DLTreeError::IntegrityViolated),
+        }
+    }
+
+    pub fn set_node(&mut self, value: IT) -> Result<SyncNode<IT, LT>, DLTreeError> {
+        match self.replace(Value::Node(value))? {
+            SyncTreeElementImpl::Node(n) => Ok(SyncNode::new(n)),
+            SyncTreeElementImpl::Leaf(_) => Err(DLTreeError::IntegrityViolated),
+        }
+    }
+
+    pub fn insert_before(&mut self, value: Value<IT, LT>) -> Result<SyncTree<IT, LT>, DLTreeError> {
+        let inserted = self.update_as_child(|index, children, parent| {
+            let new_element = SyncTreeElementImpl::new(value, Some(parent));
+            children.insert(index, new_element.clone());
+            Ok(new_element)
+        })?;
+        Ok(SyncTree { tree: inserted })
+    }
+
+    pub fn insert_after(&mut self, value: Value<IT, LT>) -> Result<SyncTree<IT, LT>, DLTreeError> {
+        let inserted = self.update_as_child(|index, children, parent| {
+            let new_element = SyncTreeElementImpl::new(value, Some(parent));
+            children.insert(index + 1, new_element.clone());
+            Ok(new_element)
+        })?;
+        Ok(SyncTree { tree: inserted })
+    }
+
+    pub fn remove_from_tree(&mut self) -> Result<SyncTree<IT, LT>, DLTreeError> {
+        let removed_child = self.update_as_child(|index, children, _| {
+            let removed_child = children
+                .remove(index)
+                .ok_or(DLTreeError::IntegrityViolated)?;
+            *lock(self.element_impl.parent()) = None;
+            Ok(removed_child)
+        })?;
+        Ok(SyncTree {
+            tree: removed_child,
+        })
+    }
+}
+
+impl<IT, LT, T: SyncTreeElementTrait<IT, LT>> Clone for SyncTreeElementType<IT, LT, T> {
+    fn clone(&self) -> Self {
+        SyncTreeElementType::new(self.element_impl.clone())
+    }
+}
+
+impl<IT, LT, T: SyncTreeElementTrait<IT, LT>> PartialEq for SyncTreeElementType<IT, LT, T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.element_impl, &other.element_impl)
+    }
+}
+
+impl<IT, LT> SyncLeaf<IT, LT> {
+    pub fn value(&self) -> RwLockReadGuard<'_, LT> {
+        read(&self.element_impl.value)
+    }
+
+    pub fn value_mut(&self) -> RwLockWriteGuard<'_, LT> {
+        write(&self.element_impl.value)
+    }
+}
+
+impl<IT: Clone, LT: Clone> crate::DeepClone for SyncLeaf<IT, LT> {
+    fn deep_clone(&self) -> Self {
+        SyncLeaf::new(Arc::new(SyncLeafImpl::new(self.value().clone(), None)))
+    }
+}
+
+impl<IT, LT> SyncNode<IT, LT> {
+    pub fn push_back_child(&mut self, value: Value<IT, LT>) -> SyncTreeElement<IT, LT> {
+        let new_child = SyncTreeElementImpl::new(value, Some(Arc::downgrade(&self.element_impl)));
+        let result = SyncTreeElement::new(&new_child);
+        lock(&self.element_impl.children).push_back(new_child);
+        result
+    }
+    pub fn push_front_child(&mut self, value: Value<IT, LT>) -> SyncTreeElement<IT, LT> {
+        let new_child = SyncTreeElementImpl::new(value, Some(Arc::downgrade(&self.element_impl)));
+        let result = SyncTreeElement::new(&new_child);
+        lock(&self.element_impl.children).push_front(new_child);
+        result
+    }
+    pub fn push_child_tree(&mut self, subtree: SyncTreeElement<IT, LT>) -> SyncTreeElement<IT, LT> {
+        let new_child = match subtree {
+            SyncTreeElement::Node(n) => SyncTreeElementImpl::Node(n.element_impl),
+            SyncTreeElement::Leaf(l) => SyncTreeElementImpl::Leaf(l.element_impl),
+        };
+        let result = SyncTreeElement::new(&new_child);
+        // The parent pointer is updated while the children are locked, so concurrent child
+        // operations on the subtree never observe a parent that does not contain it
+        let mut children = lock(&self.element_impl.children);
+        *lock(new_child.parent()) = Some(Arc::downgrade(&self.element_impl));
+        children.push_back(new_child);
+        result
+    }
+    pub fn remove_all_children(&mut self) -> Result<(), DLTreeError> {
+        let mut children = lock(&self.element_impl.children);
+        for child in children.drain(..) {
+            *lock(child.parent()) = None;
+        }
+        Ok(())
+    }
+    // The comparison is done on a snapshot of the children without holding any lock,
+    // so the comparator may lock values of the children. If the children are changed
+    // concurrently, the sorting is repeated.
+    pub fn sort_children_unstable<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&SyncTreeElement<IT, LT>, &SyncTreeElement<IT, LT>) -> Ordering,
+    {
+        loop {
+            let snapshot: Vec<SyncTreeElementImpl<IT, LT>> =
+                lock(&self.element_impl.children).iter().cloned().collect();
+            let mut sorted: Vec<SyncTreeElement<IT, LT>> =
+                snapshot.iter().map(SyncTreeElement::new).collect();
+            sorted.sort_unstable_by(&mut compare);
+
+            let mut children = lock(&self.element_impl.children);
+            let unchanged = children.len() == snapshot.len()
+                && children
+                    .iter()
+                    .zip(snapshot.iter())
+                    .all(|(a, b)| a.as_ptr() == b.as_ptr());
+            if unchanged {
+                *children = sorted.into_iter().map(|c| c.into_impl()).collect();
+                return;
+            }
+        }
+    }
+    pub fn children(&self) -> Vec<SyncTreeElement<IT, LT>> {
+        lock(&self.element_impl.children)
+            .iter()
+            .map(SyncTreeElement::new)
+            .collect()
+    }
+
+    pub fn value(&self) -> RwLockReadGuard<'_, IT> {
+        read(&self.element_impl.value)
+    }
+
+    pub fn value_mut(&self) -> RwLockWriteGuard<'_, IT> {
+        write(&self.element_impl.value)
+    }
+}
+
+impl<IT: Clone, LT: Clone> crate::DeepClone for SyncNode<IT, LT> {
+    fn deep_clone(&self) -> Self {
+        let new_node = Arc::new(SyncNodeImpl::new(self.value().clone(), None));
+        let mut stack = vec![(self.element_impl.clone(), new_node.clone())];
+        while let Some((source, target)) = stack.pop() {
+            // Values are only locked after the children of the source have been released again
+            let children: Vec<SyncTreeElementImpl<IT, LT>> =
+                lock(&source.children).iter().cloned().collect();
+            let mut cloned_children = VecDeque::with_capacity(children.len());
+            for child in children {
+                let parent = Some(Arc::downgrade(&target));
+                match child {
+                    SyncTreeElementImpl::Node(n) => {
+                        let cloned = Arc::new(SyncNodeImpl::new(read(&n.value).clone(), parent));
+                        stack.push((n, cloned.clone()));
+                        cloned_children.push_back(SyncTreeElementImpl::Node(cloned));
+                    }
+                    SyncTreeElementImpl::Leaf(l) => {
+                        let cloned = Arc::new(SyncLeafImpl::new(read(&l.value).clone(), parent));
+                        cloned_children.push_back(SyncTreeElementImpl::Leaf(cloned));
+                    }
+                }
+            }
+            *lock(&target.children) = cloned_children;
+        }
+        SyncNode::new(new_node)
+    }
+}
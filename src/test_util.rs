@@ -0,0 +1,98 @@
+use crate::arena::ArenaElement;
+use crate::frozen::FrozenElement;
+use crate::persistent::PElement;
+use crate::sync::sync_tree_element::SyncTreeElement;
+use crate::tree::{Tree, Value};
+use crate::tree_elements::tree_element::TreeElement;
+use std::fmt::Display;
+
+// a(1,b(2,c(3)),d(4,e()),5)
+pub(crate) fn build_tree() -> Tree<String, u32> {
+    crate::tree!(node "a".to_string() => [
+        leaf 1,
+        node "b".to_string() => [leaf 2, node "c".to_string() => [leaf 3]],
+        node "d".to_string() => [leaf 4, node "e".to_string()],
+        leaf 5,
+    ])
+}
+
+// 1(2,3(4,5()),6)
+pub(crate) fn build_number_tree() -> Tree<i32, i32> {
+    crate::tree!(node 1 => [leaf 2, node 3 => [leaf 4, node 5], leaf 6])
+}
+
+pub(crate) fn child<IT, LT>(element: &TreeElement<IT, LT>, index: usize) -> TreeElement<IT, LT> {
+    element.as_node().unwrap().children()[index].clone()
+}
+
+// Elements that can be described as nested text
+pub(crate) trait Describe: Sized {
+    // The text of the value and the children, None for leaves
+    fn parts(&self) -> (String, Option<Vec<Self>>);
+}
+
+// Nested description of the element, e.g. a(1,b(2),3)
+pub(crate) fn describe<E: Describe>(element: &E) -> String {
+    match element.parts() {
+        (value, Some(children)) => format!(
+            "{}({})",
+            value,
+            children
+                .iter()
+                .map(describe)
+                .collect::<Vec<String>>()
+                .join(",")
+        ),
+        (value, None) => value,
+    }
+}
+
+// The text of the value of the element
+pub(crate) fn label<E: Describe>(element: &E) -> String {
+    element.parts().0
+}
+
+impl<IT: Display, LT: Display> Describe for TreeElement<IT, LT> {
+    fn parts(&self) -> (String, Option<Vec<Self>>) {
+        match self {
+            TreeElement::Node(n) => (n.value().to_string(), Some(n.children())),
+            TreeElement::Leaf(l) => (l.value().to_string(), None),
+        }
+    }
+}
+
+impl<IT: Display, LT: Display> Describe for SyncTreeElement<IT, LT> {
+    fn parts(&self) -> (String, Option<Vec<Self>>) {
+        match self {
+            SyncTreeElement::Node(n) => (n.value().to_string(), Some(n.children())),
+            SyncTreeElement::Leaf(l) => (l.value().to_string(), None),
+        }
+    }
+}
+
+impl<IT: Display, LT: Display> Describe for FrozenElement<'_, IT, LT> {
+    fn parts(&self) -> (String, Option<Vec<Self>>) {
+        match self.value() {
+            Value::Node(n) => (n.to_string(), Some(self.children().collect())),
+            Value::Leaf(l) => (l.to_string(), None),
+        }
+    }
+}
+
+impl<IT: Display, LT: Display> Describe for ArenaElement<IT, LT> {
+    fn parts(&self) -> (String, Option<Vec<Self>>) {
+        match self {
+            ArenaElement::Node(n) => (n.value().to_string(), Some(n.children())),
+            ArenaElement::Leaf(l) => (l.value().to_string(), None),
+        }
+    }
+}
+
+impl<IT: Display, LT: Display> Describe for PElement<IT, LT> {
+    fn parts(&self) -> (String, Option<Vec<Self>>) {
+        match self {
+            PElement::Node(n) => (n.value().to_string(), Some(n.children().to_vec())),
+            PElement::Leaf(l) => (l.to_string(), None),
+        }
+    }
+}
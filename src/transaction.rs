@@ -0,0 +1,191 @@
+use crate::tree::{DLTreeError, Tree};
+use crate::tree_elements::tree_element::TreeElement;
+
+// Gives access to the tree while a transaction is open.
+// All edits of the tree are part of the transaction, regardless of the handle used for them.
+pub struct Transaction<'a, IT, LT> {
+    tree: &'a Tree<IT, LT>,
+}
+
+impl<IT, LT> Transaction<'_, IT, LT> {
+    pub fn root_node(&self) -> TreeElement<IT, LT> {
+        self.tree.root_node()
+    }
+
+    pub fn tree(&self) -> &Tree<IT, LT> {
+        self.tree
+    }
+}
+
+// Rolls the transaction back if the closure panics
+struct OpenTransaction<'a, IT, LT> {
+    tree: &'a Tree<IT, LT>,
+    temporary_journal: bool,
+    finished: bool,
+}
+
+impl<IT, LT> OpenTransaction<'_, IT, LT> {
+    fn finish(&mut self, commit: bool) -> Result<(), DLTreeError> {
+        self.finished = true;
+        let result = if commit {
+            self.tree.commit_transaction()
+        } else {
+            self.tree.rollback_transaction()
+        };
+        if self.temporary_journal {
+            self.tree.disable_journal();
+        }
+        result
+    }
+}
+
+impl<IT, LT> Drop for OpenTransaction<'_, IT, LT> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.finish(false);
+        }
+    }
+}
+
+impl<IT: Clone + PartialEq, LT: Clone + PartialEq> Tree<IT, LT> {
+    // Commits all edits of the closure if it returns Ok and reverts them if it returns Err.
+    // Reverted elements are put back into the tree, so existing handles stay valid.
+    // The edits are recorded as a single undo step if the journal is enabled. Otherwise
+    // a journal without history is used for the duration of the transaction.
+    pub fn transaction<F, R, E>(&self, transaction_fn: F) -> Result<R, E>
+    where
+        F: FnOnce(&Transaction<'_, IT, LT>) -> Result<R, E>,
+        E: From<DLTreeError>,
+    {
+        let temporary_journal = !self.is_journal_enabled();
+        if temporary_journal {
+            self.enable_journal(0);
+        }
+        let mut open = OpenTransaction {
+            tree: self,
+            temporary_journal,
+            finished: false,
+        };
+        self.with_journal(|journal| {
+            journal.begin_transaction(None);
+            Ok(())
+        })?;
+        let result = transaction_fn(&Transaction { tree: self });
+        open.finish(result.is_ok())?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{build_number_tree, describe};
+    use crate::tree::{DLTreeError, Value};
+    use crate::tree_elements::tree_element::TreeElement;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[test]
+    fn rollback_test() {
+        let tree = build_number_tree();
+        let children = tree.root_node().as_node().unwrap().children();
+        let mut node3 = children[1].as_node().unwrap();
+        let leaf4 = node3.children()[0].clone();
+
+        let result: Result<(), DLTreeError> = tree.transaction(|tx| {
+            let mut root = tx.root_node().as_node().unwrap();
+            // Move node 3 to the front and edit it while it is detached
+            let mut moved = node3.remove_from_tree()?.root_node().as_node().unwrap();
+            moved.push_back_child(Value::Leaf(7));
+            moved.children()[0].clone().remove_from_tree()?;
+            *moved.value_mut() = 8;
+            root.push_child_tree(TreeElement::Node(moved));
+            root.sort_children_unstable(|a, b| b.value().cmp(&a.value()));
+            children[0].clone().set_node(9)?;
+            // Fails on the root
+            tx.root_node().remove_from_tree()?;
+            Ok(())
+        });
+        assert_eq!(result.unwrap_err(), DLTreeError::ChildOperationOnRootLevel);
+        assert_eq!(describe(&tree.root_node()), "1(2,3(4,5()),6)");
+        assert_eq!(
+            node3.parent().unwrap().unwrap(),
+            tree.root_node().as_node().unwrap()
+        );
+        assert_eq!(leaf4.parent().unwrap().unwrap(), node3);
+        assert!(children[0].parent().unwrap().is_some());
+        assert!(!tree.is_journal_enabled());
+        node3.push_back_child(Value::Leaf(10));
+        assert_eq!(describe(&tree.root_node()), "1(2,3(4,5(),10),6)");
+    }
+
+    #[test]
+    fn commit_test() -> Result<(), DLTreeError> {
+        let tree = build_number_tree();
+        tree.enable_journal(10);
+        let count = tree.transaction(|tx| {
+            let mut root = tx.root_node().as_node().unwrap();
+            root.push_back_child(Value::Leaf(7));
+            root.push_front_child(Value::Leaf(8));
+            Ok::<usize, DLTreeError>(root.children().len())
+        })?;
+        assert_eq!(count, 5);
+        assert_eq!(describe(&tree.root_node()), "1(8,2,3(4,5()),6,7)");
+
+        // Nested transactions are rolled back independently
+        tree.transaction(|tx| {
+            let mut root = tx.root_node().as_node().unwrap();
+            root.push_back_child(Value::Leaf(9));
+            let inner: Result<(), DLTreeError> = tx.tree().transaction(|_| {
+                root.push_back_child(Value::Leaf(10));
+                Err(DLTreeError::MissingRoot)
+            });
+            assert_eq!(inner, Err(DLTreeError::MissingRoot));
+            Ok::<(), DLTreeError>(())
+        })?;
+        assert_eq!(describe(&tree.root_node()), "1(8,2,3(4,5()),6,7,9)");
+
+        // Both transactions are single undo steps
+        tree.undo()?;
+        assert_eq!(describe(&tree.root_node()), "1(8,2,3(4,5()),6,7)");
+        tree.undo()?;
+        assert_eq!(describe(&tree.root_node()), "1(2,3(4,5()),6)");
+        assert!(tree.is_journal_enabled());
+        Ok(())
+    }
+
+    #[test]
+    fn panic_test() {
+        let tree = build_number_tree();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let _: Result<(), DLTreeError> = tree.transaction(|tx| {
+                tx.root_node()
+                    .as_node()
+                    .unwrap()
+                    .push_back_child(Value::Leaf(7));
+                panic!("failed")
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(describe(&tree.root_node()), "1(2,3(4,5()),6)");
+    }
+
+    #[test]
+    fn borrowed_value_test() {
+        let tree = build_number_tree();
+        let root = tree.root_node().as_node().unwrap();
+        let mut node3 = root.children()[1].as_node().unwrap();
+        let mut leaf2 = root.children()[0].clone();
+        let result: Result<(), DLTreeError> = tree.transaction(|_| {
+            node3.push_back_child(Value::Leaf(7));
+            let mut value = root.value_mut();
+            *value = 8;
+            // Changes below a borrowed value are recorded as well
+            node3.push_back_child(Value::Leaf(9));
+            node3.children()[0].clone().set_leaf(10)?;
+            // The children of the borrowed node itself cannot be changed
+            leaf2.set_leaf(11)?;
+            Ok(())
+        });
+        assert_eq!(result.unwrap_err(), DLTreeError::ValueBorrowed);
+        assert_eq!(describe(&tree.root_node()), "1(2,3(4,5()),6)");
+    }
+}
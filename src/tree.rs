@@ -1,15 +1,47 @@
 use crate::internal::tree_element_impl::TreeElementImpl;
 use crate::tree_elements::tree_element::TreeElement;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DLTreeError {
     // This error should never happen and is a bug in dltree
     // Please report any occurence of this error
     IntegrityViolated,
     // This error happens if child manipulations like insertions or replacements are applied on the root element
     ChildOperationOnRootLevel,
+    // An imported row refers to a parent that is not part of the imported data
+    MissingParent,
+    // The imported data contains more than one element without parent
+    MultipleRoots,
+    // The imported data contains no element without parent
+    MissingRoot,
+    // The same id has been used for more than one imported element
+    DuplicateId,
+    // The parent references of the imported data form a cycle
+    CycleDetected,
+    // An imported element refers to a leaf as its parent
+    LeafWithChildren,
+    // An imported nested set interval or materialized path is malformed
+    MalformedEncoding,
+    // An arena handle refers to an element that has been removed from its arena
+    StaleHandle,
+    // A node operation has been applied on a leaf or a leaf operation on a node
+    ElementKindMismatch,
+    // A child index or path refers to a child that does not exist
+    IndexOutOfRange,
+    // Undo, redo or transactions have been used on a tree without an enabled journal
+    JournalDisabled,
+    // A transaction has been committed or rolled back without being started
+    NoTransaction,
+    // Undo or redo has been requested while a transaction is still open
+    TransactionInProgress,
+    // Two elements that have been compared belong to different trees
+    DifferentTrees,
+    // The value of the changed element or of one of its ancestors is mutably borrowed,
+    // so the change could not be reported to the observers of the tree
+    ValueBorrowed,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value<IT, LT> {
     Node(IT),
     Leaf(LT),
@@ -33,8 +65,10 @@ impl<IT, LT> Tree<IT, LT> {
 #[cfg(test)]
 mod tests {
     use crate::tree::{DLTreeError, Tree, Value};
+    use crate::tree_elements::tree_element::TreeElement;
     use crate::DeepClone;
     use std::borrow::BorrowMut;
+    use std::cmp::Ordering;
 
     #[test]
     fn tree_building_test() {
@@ -86,7 +120,7 @@ mod tests {
         let _removed_leaf_34 = leaf34.remove_from_tree()?;
 
         assert_eq!(node21.children().len(), 2);
-        assert_eq!(*node21.children().get(0).unwrap().value(), 31);
+        assert_eq!(*node21.children().first().unwrap().value(), 31);
         assert_eq!(*node21.children().get(1).unwrap().value(), 33);
 
         {
@@ -248,6 +282,102 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn sort_children_test() {
+        let tree: Tree<i32, i32> = crate::tree!(node 0 => [
+            leaf 3,
+            node 2 => [leaf 9, node 8 => [leaf 7, leaf 6], leaf 5],
+            leaf 1,
+            node 1,
+            leaf 2,
+        ]);
+        let mut root = tree.root_node().as_node().unwrap();
+        let values = |node: &crate::tree_elements::tree_element_type::Node<i32, i32>| {
+            node.children()
+                .iter()
+                .map(|c| match c {
+                    TreeElement::Node(n) => format!("n{}", n.value()),
+                    TreeElement::Leaf(l) => format!("l{}", l.value()),
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        // Nodes before leaves, the order of equal elements is kept
+        root.sort_children_by(|a, b| match (a, b) {
+            (TreeElement::Node(_), TreeElement::Leaf(_)) => Ordering::Less,
+            (TreeElement::Leaf(_), TreeElement::Node(_)) => Ordering::Greater,
+            _ => Ordering::Equal,
+        });
+        assert_eq!(values(&root), "n2,n1,l3,l1,l2");
+        root.reverse_children();
+        assert_eq!(values(&root), "l2,l1,l3,n1,n2");
+        root.sort_children_by_key(|c| c.as_leaf().map(|l| *l.value()));
+        assert_eq!(values(&root), "n1,n2,l1,l2,l3");
+        let mut calls = 0;
+        root.sort_children_cached_key(|c| {
+            calls += 1;
+            c.parent().unwrap().map(|p| *p.value())
+        });
+        assert_eq!(calls, 5);
+        assert_eq!(values(&root), "n1,n2,l1,l2,l3");
+
+        root.sort_subtree_by(|a, b| match (a, b) {
+            (TreeElement::Leaf(a), TreeElement::Leaf(b)) => a.value().cmp(&b.value()),
+            (TreeElement::Node(_), TreeElement::Leaf(_)) => Ordering::Less,
+            (TreeElement::Leaf(_), TreeElement::Node(_)) => Ordering::Greater,
+            (TreeElement::Node(a), TreeElement::Node(b)) => a.value().cmp(&b.value()),
+        });
+        assert_eq!(values(&root), "n1,n2,l1,l2,l3");
+        let node2 = root.children()[1].as_node().unwrap();
+        assert_eq!(values(&node2), "n8,l5,l9");
+        assert_eq!(values(&node2.children()[0].as_node().unwrap()), "l6,l7");
+    }
+
+    #[test]
+    fn sorted_insertion_test() -> Result<(), DLTreeError> {
+        let tree: Tree<i32, i32> = crate::tree!(node 0 => [leaf 1, leaf 3, leaf 3, leaf 7]);
+        let mut root = tree.root_node().as_node().unwrap();
+        let by_value = |a: &TreeElement<i32, i32>, b: &TreeElement<i32, i32>| {
+            a.as_leaf()
+                .unwrap()
+                .value()
+                .cmp(&b.as_leaf().unwrap().value())
+        };
+        let search = |root: &crate::tree_elements::tree_element_type::Node<i32, i32>, v: i32| {
+            root.binary_search_children_by(|c| c.as_leaf().unwrap().value().cmp(&v))
+        };
+        assert_eq!(search(&root, 7), Ok(3));
+        assert_eq!(search(&root, 0), Err(0));
+        assert_eq!(search(&root, 5), Err(3));
+        assert_eq!(search(&root, 8), Err(4));
+
+        let five = root.insert_child_sorted_by(Value::Leaf(5), by_value)?;
+        assert_eq!(five.parent()?, Some(root.clone()));
+        // Equal elements are inserted behind the existing ones
+        let three = root.insert_child_sorted_by(Value::Leaf(3), by_value)?;
+        root.insert_child_sorted_by(Value::Leaf(0), by_value)?;
+        root.insert_child_sorted_by(Value::Leaf(9), by_value)?;
+        let children = root.children();
+        let values: Vec<i32> = children
+            .iter()
+            .map(|c| *c.as_leaf().unwrap().value())
+            .collect();
+        assert_eq!(values, vec![0, 1, 3, 3, 3, 5, 7, 9]);
+        assert!(children[4].ptr_eq(&three));
+
+        // The comparators can access the node whose children are compared
+        let counter = root.clone();
+        let count = |a: &TreeElement<i32, i32>, b: &TreeElement<i32, i32>| {
+            *counter.value_mut() += 1;
+            by_value(a, b)
+        };
+        root.insert_child_sorted_by(Value::Leaf(4), count)?;
+        assert!(root.binary_search_children_by(|c| count(c, &five)).is_ok());
+        assert!(*root.value() > 0);
+        Ok(())
+    }
+
     #[test]
     fn deep_clone_test() -> Result<(), DLTreeError> {
         // Create a new tree with three levels under the root node
@@ -280,7 +410,7 @@ mod tests {
         assert_eq!(*cloned_node.value(), 112);
 
         assert_eq!(cloned_node.children().len(), 3);
-        let cloned_child_2a = cloned_node.children().get(0).unwrap().as_leaf().unwrap();
+        let cloned_child_2a = cloned_node.children().first().unwrap().as_leaf().unwrap();
         let cloned_child_2b = cloned_node.children().get(1).unwrap().as_leaf().unwrap();
         let cloned_child_2c = cloned_node.children().get(2).unwrap().as_node().unwrap();
 
@@ -300,7 +430,7 @@ mod tests {
         assert_eq!(cloned_child_2c.children().len(), 2);
         let cloned_child_3a = cloned_child_2c
             .children()
-            .get(0)
+            .first()
             .unwrap()
             .as_leaf()
             .unwrap();
@@ -1,2 +1,5 @@
 pub mod tree_element;
 pub mod tree_element_type;
+pub mod value_mut;
+pub mod weak_tree_element;
+pub mod weak_tree_element_type;
@@ -1,8 +1,10 @@
 use crate::internal::tree_element_impl::TreeElementImpl;
 use crate::tree::{DLTreeError, Tree, Value};
 use crate::tree_elements::tree_element_type::{Leaf, Node};
+use crate::tree_elements::value_mut::ValueMut;
+use crate::tree_elements::weak_tree_element::WeakTreeElement;
 use crate::DeepClone;
-use std::cell::{Ref, RefMut};
+use std::cell::Ref;
 
 #[derive(Debug)]
 pub enum TreeElement<IT, LT> {
@@ -17,6 +19,12 @@ impl<IT, LT> TreeElement<IT, LT> {
             TreeElementImpl::Leaf(l) => TreeElement::Leaf(Leaf::new(l.clone())),
         }
     }
+    pub(crate) fn to_impl(&self) -> TreeElementImpl<IT, LT> {
+        match self {
+            TreeElement::Node(n) => n.to_impl(),
+            TreeElement::Leaf(l) => l.to_impl(),
+        }
+    }
     pub fn as_node(&self) -> Option<Node<IT, LT>> {
         match self {
             TreeElement::Node(n) => Some(n.clone()),
@@ -35,6 +43,12 @@ impl<IT, LT> TreeElement<IT, LT> {
             TreeElement::Leaf(l) => l.parent(),
         }
     }
+    pub fn downgrade(&self) -> WeakTreeElement<IT, LT> {
+        match self {
+            TreeElement::Node(n) => WeakTreeElement::Node(n.downgrade()),
+            TreeElement::Leaf(l) => WeakTreeElement::Leaf(l.downgrade()),
+        }
+    }
     pub fn remove_from_tree(&mut self) -> Result<Tree<IT, LT>, DLTreeError> {
         match self {
             TreeElement::Node(n) => n.remove_from_tree(),
@@ -80,13 +94,13 @@ impl<IT: Clone, LT: Clone> DeepClone for TreeElement<IT, LT> {
 }
 
 impl<T> TreeElement<T, T> {
-    pub fn value(&self) -> Ref<T> {
+    pub fn value(&self) -> Ref<'_, T> {
         match self {
             TreeElement::Node(n) => n.value(),
             TreeElement::Leaf(l) => l.value(),
         }
     }
-    pub fn value_mut(&mut self) -> RefMut<T> {
+    pub fn value_mut(&mut self) -> ValueMut<'_, T, T, T> {
         match self {
             TreeElement::Node(n) => n.value_mut(),
             TreeElement::Leaf(l) => l.value_mut(),
@@ -1,18 +1,28 @@
 use crate::internal::leaf_impl::LeafImpl;
+use crate::internal::metrics;
 use crate::internal::node_impl::NodeImpl;
+use crate::internal::observers::Recipients;
 use crate::internal::tree_element_impl::TreeElementImpl;
 use crate::internal::TreeElementTrait;
+use crate::journal::Edit;
+use crate::observer::TreeEvent;
 use crate::tree::{DLTreeError, Tree, Value};
 use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::value_mut::ValueMut;
+use crate::tree_elements::weak_tree_element_type::WeakTreeElementType;
 use std::cell::{Ref, RefCell, RefMut};
 use std::cmp::Ordering;
 use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::rc::{Rc, Weak};
 
+// The result of an update of the children of a parent, the parent, the index of the updated
+// child and the recipients of the change
+type ChildUpdate<IT, LT, R> = (R, Node<IT, LT>, usize, Recipients<IT, LT>);
+
 #[derive(Debug)]
 pub struct TreeElementType<IT, LT, T: TreeElementTrait<IT, LT>> {
-    element_impl: Rc<RefCell<T>>,
+    pub(crate) element_impl: Rc<RefCell<T>>,
     phantom_it: PhantomData<IT>,
     phantom_lt: PhantomData<LT>,
 }
@@ -24,11 +34,15 @@ impl<IT, LT, T: TreeElementTrait<IT, LT>> TreeElementType<IT, LT, T> {
     pub fn new(value: Rc<RefCell<T>>) -> Self {
         TreeElementType {
             element_impl: value,
-            phantom_it: PhantomData::default(),
-            phantom_lt: PhantomData::default(),
+            phantom_it: PhantomData,
+            phantom_lt: PhantomData,
         }
     }
 
+    pub(crate) fn to_impl(&self) -> TreeElementImpl<IT, LT> {
+        T::to_impl(&self.element_impl)
+    }
+
     fn is_same(&self, other: &TreeElementImpl<IT, LT>) -> bool {
         let other_ptr = match other {
             TreeElementImpl::Node(n) => n.as_ptr() as *mut T,
@@ -37,7 +51,10 @@ impl<IT, LT, T: TreeElementTrait<IT, LT>> TreeElementType<IT, LT, T> {
         self.element_impl.as_ptr() == other_ptr
     }
 
-    fn update_as_child<F, R>(&self, update_fn: F) -> Result<R, DLTreeError>
+    // Returns the result of update_fn together with the parent, the index of this element and
+    // the recipients of the change. No borrow is held when this function returns, so observers
+    // can be notified afterwards.
+    fn update_as_child<F, R>(&self, update_fn: F) -> Result<ChildUpdate<IT, LT, R>, DLTreeError>
     where
         F: FnOnce(
             usize,
@@ -49,6 +66,7 @@ impl<IT, LT, T: TreeElementTrait<IT, LT>> TreeElementType<IT, LT, T> {
             .parent()?
             .ok_or(DLTreeError::ChildOperationOnRootLevel)?
             .element_impl;
+        let recipients = Recipients::collect(&TreeElementImpl::Node(parent.clone()))?;
         let index = parent
             .borrow()
             .children
@@ -57,11 +75,12 @@ impl<IT, LT, T: TreeElementTrait<IT, LT>> TreeElementType<IT, LT, T> {
             .find(|(_, child)| self.is_same(child))
             .ok_or(DLTreeError::IntegrityViolated)?
             .0;
-        return update_fn(
+        let result = update_fn(
             index,
             &mut parent.borrow_mut().children,
             Rc::downgrade(&parent),
-        );
+        )?;
+        Ok((result, Node::new(parent), index, recipients))
     }
 
     pub fn parent(&self) -> Result<Option<Node<IT, LT>>, DLTreeError> {
@@ -79,82 +98,80 @@ impl<IT, LT, T: TreeElementTrait<IT, LT>> TreeElementType<IT, LT, T> {
             },
         }
     }
+    pub fn downgrade(&self) -> WeakTreeElementType<IT, LT, T> {
+        WeakTreeElementType::new(Rc::downgrade(&self.element_impl))
+    }
+    // Replaces this element in its parent. The replaced element is detached from the tree.
+    fn replace(&mut self, value: Value<IT, LT>) -> Result<TreeElement<IT, LT>, DLTreeError> {
+        let (new, parent, index, recipients) =
+            self.update_as_child(|index, children, parent| {
+                let child = children
+                    .get_mut(index)
+                    .ok_or(DLTreeError::IntegrityViolated)?;
+                *child = TreeElementImpl::new(value, Some(parent));
+                Ok(TreeElement::new(child))
+            })?;
+        *self.element_impl.borrow_mut().parent() = None;
+        metrics::update(
+            &parent.element_impl,
+            Some(&self.to_impl()),
+            Some(&new.to_impl()),
+        );
+        parent.report_replaced(&recipients, index, &self.to_impl(), &new.to_impl());
+        Ok(new)
+    }
+
     pub fn set(&mut self, value: Value<IT, LT>) -> Result<TreeElement<IT, LT>, DLTreeError> {
-        self.update_as_child(|index, children, parent| {
-            let child = children
-                .get_mut(index)
-                .ok_or(DLTreeError::IntegrityViolated)?;
-            match value {
-                Value::Node(n) => {
-                    let node = Rc::new(RefCell::new(NodeImpl::new(n, Some(parent))));
-                    *child = TreeElementImpl::Node(node.clone());
-                    Ok(TreeElement::new(child))
-                }
-                Value::Leaf(l) => {
-                    let leaf = Rc::new(RefCell::new(LeafImpl::new(l, Some(parent))));
-                    *child = TreeElementImpl::Leaf(leaf.clone());
-                    Ok(TreeElement::new(child))
-                }
-            }
-        })
+        self.replace(value)
     }
 
     pub fn set_leaf(&mut self, value: LT) -> Result<Leaf<IT, LT>, DLTreeError> {
-        self.update_as_child(|index, children, parent| {
-            let child = children
-                .get_mut(index)
-                .ok_or(DLTreeError::IntegrityViolated)?;
-            let leaf = Rc::new(RefCell::new(LeafImpl::new(value, Some(parent))));
-            *child = TreeElementImpl::Leaf(leaf.clone());
-            Ok(Leaf {
-                element_impl: leaf,
-                phantom_it: Default::default(),
-                phantom_lt: Default::default(),
-            })
-        })
+        self.replace(Value::Leaf(value))?
+            .as_leaf()
+            .ok_or(DLTreeError::IntegrityViolated)
     }
 
     pub fn set_node(&mut self, value: IT) -> Result<Node<IT, LT>, DLTreeError> {
-        self.update_as_child(|index, children, parent| {
-            let child = children
-                .get_mut(index)
-                .ok_or(DLTreeError::IntegrityViolated)?;
-            let node = Rc::new(RefCell::new(NodeImpl::new(value, Some(parent))));
-            *child = TreeElementImpl::Node(node.clone());
-            Ok(Node {
-                element_impl: node,
-                phantom_it: Default::default(),
-                phantom_lt: Default::default(),
-            })
-        })
+        self.replace(Value::Node(value))?
+            .as_node()
+            .ok_or(DLTreeError::IntegrityViolated)
     }
 
     pub fn insert_before(&mut self, value: Value<IT, LT>) -> Result<Tree<IT, LT>, DLTreeError> {
-        let inserted = self.update_as_child(|index, children, parent| {
-            let new_element = TreeElementImpl::new(value, Some(parent));
-            children.insert(index, new_element.clone());
-            Ok(new_element)
-        })?;
+        let (inserted, parent, index, recipients) =
+            self.update_as_child(|index, children, parent| {
+                let new_element = TreeElementImpl::new(value, Some(parent));
+                children.insert(index, new_element.clone());
+                Ok(new_element)
+            })?;
+        metrics::update(&parent.element_impl, None, Some(&inserted));
+        parent.report_inserted(&recipients, index, &inserted);
         Ok(Tree { tree: inserted })
     }
 
     pub fn insert_after(&mut self, value: Value<IT, LT>) -> Result<Tree<IT, LT>, DLTreeError> {
-        let inserted = self.update_as_child(|index, children, parent| {
-            let new_element = TreeElementImpl::new(value, Some(parent));
-            children.insert(index + 1, new_element.clone());
-            Ok(new_element)
-        })?;
+        let (inserted, parent, index, recipients) =
+            self.update_as_child(|index, children, parent| {
+                let new_element = TreeElementImpl::new(value, Some(parent));
+                children.insert(index + 1, new_element.clone());
+                Ok(new_element)
+            })?;
+        metrics::update(&parent.element_impl, None, Some(&inserted));
+        parent.report_inserted(&recipients, index + 1, &inserted);
         Ok(Tree { tree: inserted })
     }
 
     pub fn remove_from_tree(&mut self) -> Result<Tree<IT, LT>, DLTreeError> {
-        let removed_child = self.update_as_child(|index, children, _| {
-            let removed_child = children
-                .remove(index)
-                .ok_or(DLTreeError::IntegrityViolated)?;
-            *self.element_impl.borrow_mut().parent() = None;
-            Ok(removed_child)
-        })?;
+        let (removed_child, parent, index, recipients) =
+            self.update_as_child(|index, children, _| {
+                let removed_child = children
+                    .remove(index)
+                    .ok_or(DLTreeError::IntegrityViolated)?;
+                *self.element_impl.borrow_mut().parent() = None;
+                Ok(removed_child)
+            })?;
+        metrics::update(&parent.element_impl, Some(&removed_child), None);
+        parent.report_removed(&recipients, index, &removed_child);
         Ok(Tree {
             tree: removed_child,
         })
@@ -174,12 +191,19 @@ impl<IT, LT, T: TreeElementTrait<IT, LT>> PartialEq for TreeElementType<IT, LT,
 }
 
 impl<IT, LT> Leaf<IT, LT> {
-    pub fn value(&self) -> Ref<LT> {
+    pub fn value(&self) -> Ref<'_, LT> {
         Ref::map(self.element_impl.borrow(), |l| &l.value)
     }
 
-    pub fn value_mut(&self) -> RefMut<LT> {
-        RefMut::map(self.element_impl.borrow_mut(), |l| &mut l.value)
+    pub fn value_mut(&self) -> ValueMut<'_, IT, LT, LT> {
+        ValueMut::new(
+            || TreeElementImpl::Leaf(self.element_impl.clone()),
+            || {
+                let leaf = self.element_impl.try_borrow_mut()?;
+                Ok(RefMut::map(leaf, |l| &mut l.value))
+            },
+        )
+        .expect("The value is already borrowed")
     }
 }
 
@@ -194,54 +218,329 @@ impl<IT: Clone, LT: Clone> crate::DeepClone for Leaf<IT, LT> {
 }
 
 impl<IT, LT> Node<IT, LT> {
+    fn children_len(&self) -> usize {
+        self.element_impl.borrow().children.len()
+    }
+    // The recipients of changes of the children of this node
+    fn recipients(&self) -> Result<Recipients<IT, LT>, DLTreeError> {
+        Recipients::collect(&self.to_impl())
+    }
+    // Operations without result borrow the node mutably afterwards, which panics like a RefCell
+    // if the node itself is mutably borrowed. Borrowed ancestors do not prevent any change.
+    fn recipients_or_default(&self) -> Recipients<IT, LT> {
+        self.recipients().unwrap_or_default()
+    }
+    // Records the change in the journals and notifies the observers
+    fn report_inserted(
+        &self,
+        recipients: &Recipients<IT, LT>,
+        index: usize,
+        child: &TreeElementImpl<IT, LT>,
+    ) {
+        recipients.report(
+            || Edit::Insert {
+                parent: self.downgrade(),
+                index,
+                element: child.clone(),
+            },
+            || TreeEvent::ChildInserted {
+                parent: self.clone(),
+                index,
+            },
+        );
+    }
+    fn report_removed(
+        &self,
+        recipients: &Recipients<IT, LT>,
+        index: usize,
+        removed: &TreeElementImpl<IT, LT>,
+    ) {
+        recipients.report(
+            || Edit::Remove {
+                parent: self.downgrade(),
+                index,
+                element: removed.clone(),
+            },
+            || TreeEvent::ChildRemoved {
+                parent: self.clone(),
+                index,
+                removed: TreeElement::new(removed),
+            },
+        );
+    }
+    fn report_replaced(
+        &self,
+        recipients: &Recipients<IT, LT>,
+        index: usize,
+        old: &TreeElementImpl<IT, LT>,
+        new: &TreeElementImpl<IT, LT>,
+    ) {
+        recipients.report(
+            || Edit::Replace {
+                parent: self.downgrade(),
+                index,
+                old: old.clone(),
+                new: new.clone(),
+            },
+            || TreeEvent::ElementReplaced {
+                parent: self.clone(),
+                index,
+                old: TreeElement::new(old),
+                new: TreeElement::new(new),
+            },
+        );
+    }
+    fn report_reordered(
+        &self,
+        recipients: &Recipients<IT, LT>,
+        previous_order: &[TreeElementImpl<IT, LT>],
+    ) {
+        recipients.report(
+            || Edit::Reorder {
+                parent: self.downgrade(),
+                order: previous_order.to_vec(),
+            },
+            || TreeEvent::ChildrenReordered {
+                parent: self.clone(),
+                previous_order: previous_order.iter().map(TreeElement::new).collect(),
+            },
+        );
+    }
+    // Child level primitives that are used to replay recorded edits.
+    // They update parent pointers and notify observers like the public operations.
+    pub(crate) fn insert_child_at(
+        &mut self,
+        index: usize,
+        mut child: TreeElementImpl<IT, LT>,
+    ) -> Result<(), DLTreeError> {
+        if index > self.children_len() {
+            return Err(DLTreeError::IntegrityViolated);
+        }
+        let recipients = self.recipients()?;
+        child.update_parent(Some(Rc::downgrade(&self.element_impl)));
+        child.observers_mut().detached_journal = None;
+        self.element_impl
+            .borrow_mut()
+            .children
+            .insert(index, child.clone());
+        metrics::update(&self.element_impl, None, Some(&child));
+        self.report_inserted(&recipients, index, &child);
+        Ok(())
+    }
+    // Removes the child at index after checking that it is the expected element
+    pub(crate) fn remove_child_at(
+        &mut self,
+        index: usize,
+        expected: &TreeElementImpl<IT, LT>,
+    ) -> Result<(), DLTreeError> {
+        let recipients = self.recipients()?;
+        let removed = {
+            let mut node = self.element_impl.borrow_mut();
+            match node.children.get(index) {
+                Some(child) if child.ptr_eq(expected) => node.children.remove(index),
+                _ => None,
+            }
+        };
+        let mut removed = removed.ok_or(DLTreeError::IntegrityViolated)?;
+        removed.update_parent(None);
+        metrics::update(&self.element_impl, Some(&removed), None);
+        self.report_removed(&recipients, index, &removed);
+        Ok(())
+    }
+    // Replaces the child at index after checking that it is the expected element
+    pub(crate) fn replace_child_at(
+        &mut self,
+        index: usize,
+        expected: &TreeElementImpl<IT, LT>,
+        mut replacement: TreeElementImpl<IT, LT>,
+    ) -> Result<(), DLTreeError> {
+        let recipients = self.recipients()?;
+        replacement.update_parent(Some(Rc::downgrade(&self.element_impl)));
+        let replaced = {
+            let mut node = self.element_impl.borrow_mut();
+            match node.children.get_mut(index) {
+                Some(child) if child.ptr_eq(expected) => {
+                    Some(std::mem::replace(child, replacement.clone()))
+                }
+                _ => None,
+            }
+        };
+        let mut replaced = match replaced {
+            Some(replaced) => replaced,
+            None => {
+                replacement.update_parent(None);
+                return Err(DLTreeError::IntegrityViolated);
+            }
+        };
+        replaced.update_parent(None);
+        metrics::update(&self.element_impl, Some(&replaced), Some(&replacement));
+        self.report_replaced(&recipients, index, &replaced, &replacement);
+        Ok(())
+    }
+    // Replaces the order of the children by the given order of the same elements
+    // and returns the previous order
+    pub(crate) fn reorder_children(
+        &mut self,
+        order: Vec<TreeElementImpl<IT, LT>>,
+    ) -> Result<Vec<TreeElementImpl<IT, LT>>, DLTreeError> {
+        let recipients = self.recipients()?;
+        let previous_order: Vec<TreeElementImpl<IT, LT>> = {
+            let node = self.element_impl.borrow();
+            let same_elements = node.children.len() == order.len()
+                && order
+                    .iter()
+                    .all(|o| node.children.iter().any(|c| c.ptr_eq(o)));
+            if !same_elements {
+                return Err(DLTreeError::IntegrityViolated);
+            }
+            node.children.iter().cloned().collect()
+        };
+        self.element_impl.borrow_mut().children = order.into_iter().collect();
+        self.report_reordered(&recipients, &previous_order);
+        Ok(previous_order)
+    }
     pub fn push_back_child(&mut self, value: Value<IT, LT>) -> TreeElement<IT, LT> {
+        let recipients = self.recipients_or_default();
         let new_child = TreeElementImpl::new(value, Some(Rc::downgrade(&self.element_impl)));
         let result = TreeElement::new(&new_child);
-        self.element_impl.borrow_mut().children.push_back(new_child);
+        self.element_impl
+            .borrow_mut()
+            .children
+            .push_back(new_child.clone());
+        metrics::update(&self.element_impl, None, Some(&new_child));
+        self.report_inserted(&recipients, self.children_len() - 1, &new_child);
         result
     }
     pub fn push_front_child(&mut self, value: Value<IT, LT>) -> TreeElement<IT, LT> {
+        let recipients = self.recipients_or_default();
         let new_child = TreeElementImpl::new(value, Some(Rc::downgrade(&self.element_impl)));
         let result = TreeElement::new(&new_child);
-        self.element_impl.borrow_mut().children.push_front(new_child);
+        self.element_impl
+            .borrow_mut()
+            .children
+            .push_front(new_child.clone());
+        metrics::update(&self.element_impl, None, Some(&new_child));
+        self.report_inserted(&recipients, 0, &new_child);
         result
     }
     pub fn push_child_tree(&mut self, subtree: TreeElement<IT, LT>) -> TreeElement<IT, LT> {
-        let new_child = match subtree {
-            TreeElement::Node(n) => {
-                n.element_impl.borrow_mut().parent = Some(Rc::downgrade(&self.element_impl));
-                TreeElementImpl::Node(n.element_impl)
-            }
-            TreeElement::Leaf(l) => {
-                l.element_impl.borrow_mut().parent = Some(Rc::downgrade(&self.element_impl));
-                TreeElementImpl::Leaf(l.element_impl)
-            }
-        };
+        let recipients = self.recipients_or_default();
+        let mut new_child = subtree.to_impl();
+        new_child.update_parent(Some(Rc::downgrade(&self.element_impl)));
+        new_child.observers_mut().detached_journal = None;
         let result = TreeElement::new(&new_child);
-        self.element_impl.borrow_mut().children.push_back(new_child);
+        self.element_impl
+            .borrow_mut()
+            .children
+            .push_back(new_child.clone());
+        metrics::update(&self.element_impl, None, Some(&new_child));
+        self.report_inserted(&recipients, self.children_len() - 1, &new_child);
         result
     }
     pub fn remove_all_children(&mut self) -> Result<(), DLTreeError> {
+        let recipients = self.recipients()?;
         let mut removed_children = vec![];
-        while let Some(mut child) = self.element_impl.borrow_mut().children.pop_back() {
-            match &mut child {
-                TreeElementImpl::Node(n) => n.borrow_mut().parent = None,
-                TreeElementImpl::Leaf(l) => l.borrow_mut().parent = None,
-            }
+        loop {
+            // The borrow of the children must end before observers are notified
+            let popped = self.element_impl.borrow_mut().children.pop_back();
+            let mut child = match popped {
+                Some(child) => child,
+                None => break,
+            };
+            child.update_parent(None);
+            metrics::update(&self.element_impl, Some(&child), None);
+            self.report_removed(&recipients, self.children_len(), &child);
             removed_children.push(child);
         }
         assert!(self.element_impl.borrow_mut().children.is_empty());
         Ok(())
     }
-    pub fn sort_children_unstable<F>(&mut self, mut compare: F)
+    // Reorders the children with reorder_fn. The children are not borrowed while reorder_fn runs,
+    // so comparators can access the tree.
+    fn reorder_children_with<F>(&mut self, reorder_fn: F)
+    where
+        F: FnOnce(&mut Vec<TreeElement<IT, LT>>),
+    {
+        let recipients = self.recipients_or_default();
+        let mut children = self.children();
+        let previous_order: Vec<TreeElementImpl<IT, LT>> = if !recipients.is_empty() {
+            children.iter().map(TreeElement::to_impl).collect()
+        } else {
+            vec![]
+        };
+        reorder_fn(&mut children);
+        self.element_impl.borrow_mut().children =
+            children.iter().map(TreeElement::to_impl).collect();
+        self.report_reordered(&recipients, &previous_order);
+    }
+    pub fn sort_children_unstable<F>(&mut self, compare: F)
     where
         F: FnMut(&TreeElement<IT, LT>, &TreeElement<IT, LT>) -> Ordering,
     {
-        self.element_impl
-            .borrow_mut()
-            .children
-            .make_contiguous()
-            .sort_unstable_by(|a, b| compare(&TreeElement::new(a), &TreeElement::new(b)));
+        self.reorder_children_with(|children| children.sort_unstable_by(compare));
+    }
+    // Children that compare equal keep their order
+    pub fn sort_children_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&TreeElement<IT, LT>, &TreeElement<IT, LT>) -> Ordering,
+    {
+        self.reorder_children_with(|children| children.sort_by(compare));
+    }
+    pub fn sort_children_by_key<K, F>(&mut self, key: F)
+    where
+        F: FnMut(&TreeElement<IT, LT>) -> K,
+        K: Ord,
+    {
+        self.reorder_children_with(|children| children.sort_by_key(key));
+    }
+    // The key is calculated only once per child, which is faster for expensive keys
+    pub fn sort_children_cached_key<K, F>(&mut self, key: F)
+    where
+        F: FnMut(&TreeElement<IT, LT>) -> K,
+        K: Ord,
+    {
+        self.reorder_children_with(|children| children.sort_by_cached_key(key));
+    }
+    pub fn reverse_children(&mut self) {
+        self.reorder_children_with(|children| children.reverse());
+    }
+    // Sorts the children of this node and of all nodes below it with sort_children_by
+    pub fn sort_subtree_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&TreeElement<IT, LT>, &TreeElement<IT, LT>) -> Ordering,
+    {
+        let mut stack = vec![self.clone()];
+        while let Some(mut node) = stack.pop() {
+            node.sort_children_by(&mut compare);
+            stack.extend(node.children().iter().filter_map(|c| c.as_node()));
+        }
+    }
+    // Searches the children, which have to be sorted according to compare. Returns Ok with the
+    // index of a matching child or Err with the index where a matching child could be inserted.
+    // Like for sorting, the children are not borrowed while compare runs.
+    pub fn binary_search_children_by<F>(&self, compare: F) -> Result<usize, usize>
+    where
+        F: FnMut(&TreeElement<IT, LT>) -> Ordering,
+    {
+        self.children().binary_search_by(compare)
+    }
+    // Inserts the value behind all children that are not greater, so that children sorted by
+    // compare stay sorted. compare is called with a child and the new element.
+    pub fn insert_child_sorted_by<F>(
+        &mut self,
+        value: Value<IT, LT>,
+        mut compare: F,
+    ) -> Result<TreeElement<IT, LT>, DLTreeError>
+    where
+        F: FnMut(&TreeElement<IT, LT>, &TreeElement<IT, LT>) -> Ordering,
+    {
+        let new_child = TreeElementImpl::new(value, None);
+        let result = TreeElement::new(&new_child);
+        let index = self
+            .children()
+            .partition_point(|c| compare(c, &result) != Ordering::Greater);
+        self.insert_child_at(index, new_child)?;
+        Ok(result)
     }
     pub fn children(&self) -> Vec<TreeElement<IT, LT>> {
         self.element_impl
@@ -252,12 +551,22 @@ impl<IT, LT> Node<IT, LT> {
             .collect()
     }
 
-    pub fn value(&self) -> Ref<IT> {
+    pub fn value(&self) -> Ref<'_, IT> {
         Ref::map(self.element_impl.borrow(), |l| &l.value)
     }
 
-    pub fn value_mut(&self) -> RefMut<IT> {
-        RefMut::map(self.element_impl.borrow_mut(), |l| &mut l.value)
+    pub fn value_mut(&self) -> ValueMut<'_, IT, LT, IT> {
+        self.try_value_mut().expect("The value is already borrowed")
+    }
+
+    pub(crate) fn try_value_mut(&self) -> Result<ValueMut<'_, IT, LT, IT>, DLTreeError> {
+        ValueMut::new(
+            || TreeElementImpl::Node(self.element_impl.clone()),
+            || {
+                let node = self.element_impl.try_borrow_mut()?;
+                Ok(RefMut::map(node, |n| &mut n.value))
+            },
+        )
     }
 }
 
@@ -0,0 +1,84 @@
+use crate::internal::metrics;
+use crate::internal::observers::{BorrowedParent, Recipients, ValueSnapshot};
+use crate::internal::tree_element_impl::TreeElementImpl;
+use crate::observer::TreeEvent;
+use crate::tree::DLTreeError;
+use crate::tree_elements::tree_element::TreeElement;
+use std::cell::{BorrowMutError, RefMut};
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+// Mutable access to the value of a tree element.
+// Observers are notified about the change as soon as this guard is dropped. Changes below the
+// element can still be made while the guard exists, their events are delivered when it is dropped.
+pub struct ValueMut<'a, IT, LT, V> {
+    value: Option<RefMut<'a, V>>,
+    element: TreeElementImpl<IT, LT>,
+    recipients: Recipients<IT, LT>,
+    // The recipients of the element as lent to its children
+    lent: Option<Rc<BorrowedParent<IT, LT>>>,
+    // Journals record the previous value when the guard is dropped, if the value has changed
+    snapshots: Vec<ValueSnapshot<IT, LT>>,
+}
+
+impl<'a, IT, LT, V> ValueMut<'a, IT, LT, V> {
+    pub(crate) fn new<F, B>(element: F, borrow: B) -> Result<Self, DLTreeError>
+    where
+        F: FnOnce() -> TreeElementImpl<IT, LT>,
+        B: FnOnce() -> Result<RefMut<'a, V>, BorrowMutError>,
+    {
+        let element = element();
+        // If the element itself is mutably borrowed, borrow fails below like a RefCell
+        let recipients = Recipients::collect(&element).unwrap_or_default();
+        let snapshots = recipients.snapshot_value(&element);
+        let lent = recipients.lend(&element);
+        Ok(ValueMut {
+            value: Some(borrow().map_err(|_| DLTreeError::ValueBorrowed)?),
+            element,
+            recipients,
+            lent,
+            snapshots,
+        })
+    }
+}
+
+impl<IT, LT, V> Deref for ValueMut<'_, IT, LT, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value
+            .as_ref()
+            .expect("The value is only released when the guard is dropped")
+    }
+}
+
+impl<IT, LT, V> DerefMut for ValueMut<'_, IT, LT, V> {
+    fn deref_mut(&mut self) -> &mut V {
+        self.value
+            .as_mut()
+            .expect("The value is only released when the guard is dropped")
+    }
+}
+
+impl<IT, LT, V> Drop for ValueMut<'_, IT, LT, V> {
+    fn drop(&mut self) {
+        // The borrow has to be released first, so that observers can read the new value
+        self.value.take();
+        // Changes below the element could not update its cached metrics while it was borrowed
+        if let TreeElementImpl::Node(n) = &self.element {
+            metrics::refresh(n);
+        }
+        if let Some(lent) = self.lent.take() {
+            lent.release();
+        }
+        for (journal, value) in self.snapshots.drain(..) {
+            if let Ok(mut journal) = journal.try_borrow_mut() {
+                journal.record_value(&self.element, value);
+            }
+        }
+        let element = &self.element;
+        self.recipients.notify(|| TreeEvent::ValueChanged {
+            element: TreeElement::new(element),
+        });
+    }
+}
@@ -0,0 +1,74 @@
+use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::weak_tree_element_type::{WeakLeaf, WeakNode};
+
+#[derive(Debug)]
+pub enum WeakTreeElement<IT, LT> {
+    Node(WeakNode<IT, LT>),
+    Leaf(WeakLeaf<IT, LT>),
+}
+
+impl<IT, LT> WeakTreeElement<IT, LT> {
+    pub fn upgrade(&self) -> Option<TreeElement<IT, LT>> {
+        match self {
+            WeakTreeElement::Node(n) => n.upgrade().map(TreeElement::Node),
+            WeakTreeElement::Leaf(l) => l.upgrade().map(TreeElement::Leaf),
+        }
+    }
+    pub fn is_alive(&self) -> bool {
+        match self {
+            WeakTreeElement::Node(n) => n.is_alive(),
+            WeakTreeElement::Leaf(l) => l.is_alive(),
+        }
+    }
+    pub fn is_attached(&self) -> bool {
+        match self {
+            WeakTreeElement::Node(n) => n.is_attached(),
+            WeakTreeElement::Leaf(l) => l.is_attached(),
+        }
+    }
+}
+
+impl<IT, LT> Clone for WeakTreeElement<IT, LT> {
+    fn clone(&self) -> Self {
+        match &self {
+            WeakTreeElement::Node(n) => WeakTreeElement::Node(n.clone()),
+            WeakTreeElement::Leaf(l) => WeakTreeElement::Leaf(l.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tree::{DLTreeError, Tree, Value};
+
+    #[test]
+    fn weak_handle_test() -> Result<(), DLTreeError> {
+        let tree = Tree::new(Value::Node(1));
+        let mut root = tree.root_node().as_node().unwrap();
+        let mut node = root.push_back_child(Value::Node(2)).as_node().unwrap();
+        let leaf = node.push_back_child(Value::Leaf(3));
+
+        let weak_root = tree.root_node().downgrade();
+        let weak_node = node.downgrade();
+        let weak_leaf = leaf.downgrade();
+        drop(leaf);
+        assert!(weak_root.is_alive() && !weak_root.is_attached());
+        assert!(weak_node.is_alive() && weak_node.is_attached());
+        assert!(weak_leaf.is_alive() && weak_leaf.is_attached());
+        assert_eq!(*weak_leaf.upgrade().unwrap().as_leaf().unwrap().value(), 3);
+        assert_eq!(weak_node.upgrade().unwrap(), node);
+
+        // A removed subtree stays alive as long as the returned tree exists
+        let removed = node.remove_from_tree()?;
+        assert!(weak_node.is_alive() && !weak_node.is_attached());
+        assert!(weak_leaf.is_alive() && weak_leaf.is_attached());
+        drop(node);
+        drop(removed);
+        assert!(!weak_node.is_alive() && !weak_node.is_attached());
+        assert!(!weak_leaf.is_alive() && weak_leaf.upgrade().is_none());
+        assert!(weak_root.upgrade().is_some());
+        assert_eq!(root.children().len(), 0);
+        root.push_back_child(Value::Leaf(4));
+        Ok(())
+    }
+}
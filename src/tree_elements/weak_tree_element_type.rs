@@ -0,0 +1,54 @@
+use crate::internal::leaf_impl::LeafImpl;
+use crate::internal::node_impl::NodeImpl;
+use crate::internal::TreeElementTrait;
+use crate::tree_elements::tree_element_type::TreeElementType;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Weak;
+
+#[derive(Debug)]
+pub struct WeakTreeElementType<IT, LT, T: TreeElementTrait<IT, LT>> {
+    element_impl: Weak<RefCell<T>>,
+    phantom_it: PhantomData<IT>,
+    phantom_lt: PhantomData<LT>,
+}
+
+pub type WeakNode<IT, LT> = WeakTreeElementType<IT, LT, NodeImpl<IT, LT>>;
+pub type WeakLeaf<IT, LT> = WeakTreeElementType<IT, LT, LeafImpl<IT, LT>>;
+
+impl<IT, LT, T: TreeElementTrait<IT, LT>> WeakTreeElementType<IT, LT, T> {
+    pub fn new(value: Weak<RefCell<T>>) -> Self {
+        WeakTreeElementType {
+            element_impl: value,
+            phantom_it: PhantomData,
+            phantom_lt: PhantomData,
+        }
+    }
+
+    pub fn upgrade(&self) -> Option<TreeElementType<IT, LT, T>> {
+        self.element_impl.upgrade().map(TreeElementType::new)
+    }
+
+    // The element is alive as long as any strong handle or its parent refers to it
+    pub fn is_alive(&self) -> bool {
+        self.element_impl.strong_count() > 0
+    }
+
+    // The element is attached if it is alive and has a parent
+    pub fn is_attached(&self) -> bool {
+        self.upgrade()
+            .map_or(false, |element| matches!(element.parent(), Ok(Some(_))))
+    }
+}
+
+impl<IT, LT, T: TreeElementTrait<IT, LT>> Clone for WeakTreeElementType<IT, LT, T> {
+    fn clone(&self) -> Self {
+        WeakTreeElementType::new(self.element_impl.clone())
+    }
+}
+
+impl<IT, LT, T: TreeElementTrait<IT, LT>> PartialEq for WeakTreeElementType<IT, LT, T> {
+    fn eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.element_impl, &other.element_impl)
+    }
+}
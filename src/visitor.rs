@@ -0,0 +1,283 @@
+use crate::tree::Tree;
+use crate::tree_elements::tree_element::TreeElement;
+use crate::tree_elements::tree_element_type::{Leaf, Node};
+use crate::tree_elements::value_mut::ValueMut;
+use std::cell::Ref;
+use std::ops::{Deref, DerefMut};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitControl {
+    Continue,
+    // The children of the entered node are not visited, but the node is still left.
+    // Equivalent to Continue for leaves.
+    SkipChildren,
+    // Ends the walk immediately. Nodes that have been entered are not left anymore.
+    Stop,
+}
+
+pub trait TreeVisitor<IT, LT> {
+    fn enter_node(&mut self, _node: &Node<IT, LT>) -> VisitControl {
+        VisitControl::Continue
+    }
+    fn leave_node(&mut self, _node: &Node<IT, LT>) {}
+    fn visit_leaf(&mut self, _leaf: &Leaf<IT, LT>) -> VisitControl {
+        VisitControl::Continue
+    }
+}
+
+// Visits the values of the elements mutably. Observers are notified about every changed value.
+pub trait TreeVisitorMut<IT, LT> {
+    fn enter_node(&mut self, _value: &mut VisitedValue<'_, IT, LT, IT>) -> VisitControl {
+        VisitControl::Continue
+    }
+    fn leave_node(&mut self, _value: &mut VisitedValue<'_, IT, LT, IT>) {}
+    fn visit_leaf(&mut self, _value: &mut VisitedValue<'_, IT, LT, LT>) -> VisitControl {
+        VisitControl::Continue
+    }
+}
+
+// The value of a visited element. It is only borrowed mutably when the visitor changes it,
+// so values that are just read are not reported as changed.
+pub struct VisitedValue<'a, IT, LT, V> {
+    value: Option<Ref<'a, V>>,
+    guard: Option<ValueMut<'a, IT, LT, V>>,
+    value_mut: &'a dyn Fn() -> ValueMut<'a, IT, LT, V>,
+}
+
+impl<'a, IT, LT, V> VisitedValue<'a, IT, LT, V> {
+    fn new(value: Ref<'a, V>, value_mut: &'a dyn Fn() -> ValueMut<'a, IT, LT, V>) -> Self {
+        VisitedValue {
+            value: Some(value),
+            guard: None,
+            value_mut,
+        }
+    }
+}
+
+impl<IT, LT, V> Deref for VisitedValue<'_, IT, LT, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        match (&self.guard, &self.value) {
+            (Some(guard), _) => guard,
+            (None, Some(value)) => value,
+            (None, None) => unreachable!("The value is borrowed until the guard is taken"),
+        }
+    }
+}
+
+impl<IT, LT, V> DerefMut for VisitedValue<'_, IT, LT, V> {
+    fn deref_mut(&mut self) -> &mut V {
+        if self.guard.is_none() {
+            // The shared borrow has to be released before the value is borrowed mutably
+            self.value.take();
+            self.guard = Some((self.value_mut)());
+        }
+        self.guard.as_mut().expect("The guard has just been taken")
+    }
+}
+
+enum Step<IT, LT> {
+    Enter(TreeElement<IT, LT>),
+    Leave(Node<IT, LT>),
+}
+
+enum Visit<'a, IT, LT> {
+    EnterNode(&'a Node<IT, LT>),
+    LeaveNode(&'a Node<IT, LT>),
+    Leaf(&'a Leaf<IT, LT>),
+}
+
+// Walks the elements in depth-first order. The children of a node are determined when the
+// node is entered, so the visitor can change the tree while it is walked.
+// The control returned for LeaveNode is ignored.
+fn walk_with<IT, LT, F>(start: &TreeElement<IT, LT>, mut visit: F) -> VisitControl
+where
+    F: FnMut(Visit<'_, IT, LT>) -> VisitControl,
+{
+    let mut stack = vec![Step::Enter(start.clone())];
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Enter(TreeElement::Node(node)) => match visit(Visit::EnterNode(&node)) {
+                VisitControl::Stop => return VisitControl::Stop,
+                VisitControl::SkipChildren => {
+                    visit(Visit::LeaveNode(&node));
+                }
+                VisitControl::Continue => {
+                    let children = node.children();
+                    stack.push(Step::Leave(node));
+                    stack.extend(children.into_iter().rev().map(Step::Enter));
+                }
+            },
+            Step::Enter(TreeElement::Leaf(leaf)) => {
+                if visit(Visit::Leaf(&leaf)) == VisitControl::Stop {
+                    return VisitControl::Stop;
+                }
+            }
+            Step::Leave(node) => {
+                visit(Visit::LeaveNode(&node));
+            }
+        }
+    }
+    VisitControl::Continue
+}
+
+impl<IT, LT> TreeElement<IT, LT> {
+    // Returns Stop if the walk has been stopped by the visitor, Continue otherwise
+    pub fn walk<V: TreeVisitor<IT, LT>>(&self, visitor: &mut V) -> VisitControl {
+        walk_with(self, |visit| match visit {
+            Visit::EnterNode(node) => visitor.enter_node(node),
+            Visit::LeaveNode(node) => {
+                visitor.leave_node(node);
+                VisitControl::Continue
+            }
+            Visit::Leaf(leaf) => visitor.visit_leaf(leaf),
+        })
+    }
+
+    pub fn walk_mut<V: TreeVisitorMut<IT, LT>>(&self, visitor: &mut V) -> VisitControl {
+        walk_with(self, |visit| match visit {
+            Visit::EnterNode(node) => {
+                let value_mut = || node.value_mut();
+                let mut value = VisitedValue::new(node.value(), &value_mut);
+                visitor.enter_node(&mut value)
+            }
+            Visit::LeaveNode(node) => {
+                let value_mut = || node.value_mut();
+                visitor.leave_node(&mut VisitedValue::new(node.value(), &value_mut));
+                VisitControl::Continue
+            }
+            Visit::Leaf(leaf) => {
+                let value_mut = || leaf.value_mut();
+                let mut value = VisitedValue::new(leaf.value(), &value_mut);
+                visitor.visit_leaf(&mut value)
+            }
+        })
+    }
+}
+
+impl<IT, LT> Tree<IT, LT> {
+    pub fn walk<V: TreeVisitor<IT, LT>>(&self, visitor: &mut V) -> VisitControl {
+        self.root_node().walk(visitor)
+    }
+
+    pub fn walk_mut<V: TreeVisitorMut<IT, LT>>(&self, visitor: &mut V) -> VisitControl {
+        self.root_node().walk_mut(visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::build_tree;
+    use crate::tree::Value;
+    use crate::tree_elements::tree_element_type::{Leaf, Node};
+    use crate::visitor::{TreeVisitor, TreeVisitorMut, VisitControl, VisitedValue};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct Recorder {
+        events: Vec<String>,
+        skip: &'static str,
+        stop_at: u32,
+    }
+
+    impl TreeVisitor<String, u32> for Recorder {
+        fn enter_node(&mut self, node: &Node<String, u32>) -> VisitControl {
+            self.events.push(format!("enter {}", node.value()));
+            if *node.value() == self.skip {
+                VisitControl::SkipChildren
+            } else {
+                VisitControl::Continue
+            }
+        }
+        fn leave_node(&mut self, node: &Node<String, u32>) {
+            self.events.push(format!("leave {}", node.value()));
+        }
+        fn visit_leaf(&mut self, leaf: &Leaf<String, u32>) -> VisitControl {
+            self.events.push(format!("leaf {}", leaf.value()));
+            if *leaf.value() == self.stop_at {
+                VisitControl::Stop
+            } else {
+                VisitControl::Continue
+            }
+        }
+    }
+
+    #[test]
+    fn visitor_test() {
+        let tree = build_tree();
+        let mut recorder = Recorder {
+            events: vec![],
+            skip: "b",
+            stop_at: 4,
+        };
+        assert_eq!(tree.walk(&mut recorder), VisitControl::Stop);
+        assert_eq!(
+            recorder.events,
+            vec!["enter a", "leaf 1", "enter b", "leave b", "enter d", "leaf 4"]
+        );
+
+        let mut recorder = Recorder {
+            events: vec![],
+            skip: "",
+            stop_at: 0,
+        };
+        let b = tree.root_node().as_node().unwrap().children()[1].clone();
+        assert_eq!(b.walk(&mut recorder), VisitControl::Continue);
+        assert_eq!(
+            recorder.events,
+            vec!["enter b", "leaf 2", "enter c", "leaf 3", "leave c", "leave b"]
+        );
+    }
+
+    struct Renamer {
+        depth: usize,
+    }
+
+    impl TreeVisitorMut<String, u32> for Renamer {
+        fn enter_node(
+            &mut self,
+            value: &mut VisitedValue<'_, String, u32, String>,
+        ) -> VisitControl {
+            self.depth += 1;
+            value.push_str(&self.depth.to_string());
+            VisitControl::Continue
+        }
+        fn leave_node(&mut self, value: &mut VisitedValue<'_, String, u32, String>) {
+            assert!(value.ends_with(&self.depth.to_string()));
+            self.depth -= 1;
+        }
+        fn visit_leaf(&mut self, value: &mut VisitedValue<'_, String, u32, u32>) -> VisitControl {
+            if **value % 2 == 1 {
+                **value *= 10;
+            }
+            VisitControl::Continue
+        }
+    }
+
+    #[test]
+    fn visitor_mut_test() {
+        let tree = build_tree();
+        let events = Rc::new(Cell::new(0));
+        let counter = events.clone();
+        tree.add_observer(move |_: &_| counter.set(counter.get() + 1));
+        assert_eq!(
+            tree.walk_mut(&mut Renamer { depth: 0 }),
+            VisitControl::Continue
+        );
+        let frozen = tree.freeze();
+        let values: Vec<String> = frozen
+            .iter()
+            .map(|e| match e.value() {
+                Value::Node(n) => n.clone(),
+                Value::Leaf(l) => l.to_string(),
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec!["a1", "10", "b2", "d2", "50", "2", "c3", "4", "e3", "30"]
+        );
+        // Only the changed values are reported, values that are just read are not
+        assert_eq!(events.get(), 8);
+    }
+}